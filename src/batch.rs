@@ -0,0 +1,200 @@
+use crate::common::*;
+use crate::zombie_db::ZombieDb;
+use crate::error::Error;
+use rayon::prelude::*;
+
+/// 多个时间，CSV 中用空格分隔，如 `1 1200`，JSON 中也可以写成数组
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimeList(pub Vec<i64>);
+
+impl std::fmt::Display for TimeList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let strs: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        return write!(f, "{}", strs.join(" "));
+    }
+}
+
+impl serde::Serialize for TimeList {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TimeList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            One(i64),
+            Many(Vec<i64>),
+            Text(String),
+        }
+        return match Raw::deserialize(deserializer)? {
+            Raw::One(x) => Ok(TimeList(vec![x])),
+            Raw::Many(x) => Ok(TimeList(x)),
+            Raw::Text(x) => x.split_whitespace()
+                .map(|t| t.parse().map_err(|_| serde::de::Error::custom(format!("无效的时间 {t}"))))
+                .collect::<Result<_, _>>()
+                .map(TimeList),
+        };
+    }
+}
+
+/// 多组减速命中，CSV 中用空格分隔，如 `100:150 3000`，JSON 中也可以写成数组
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChillList(pub Vec<Chill>);
+
+impl std::fmt::Display for ChillList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let strs: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        return write!(f, "{}", strs.join(" "));
+    }
+}
+
+impl serde::Serialize for ChillList {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ChillList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Many(Vec<Chill>),
+            Text(String),
+        }
+        return match Raw::deserialize(deserializer)? {
+            Raw::Many(x) => Ok(ChillList(x)),
+            Raw::Text(x) => x.split_whitespace()
+                .map(|t| Chill::from_str(t).map_err(serde::de::Error::custom))
+                .collect::<Result<_, _>>()
+                .map(ChillList),
+        };
+    }
+}
+
+/// 批量查询中的一行
+///
+/// `zombie` 为僵尸名字（见 [`ZombieDb::resolve`]），`ice` 为所有冰时机，为空时不用冰，`chill` 为减速命中，
+/// `ice_source` 为空时冰时机为生效时间，`butter` 为黄油命中时间，`hugewave` 为空时视为普通波，
+/// `range_r` 为空时只查询 `range_l` 一个坐标，只有 `range_r` 时查询出错。
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct BatchQuery {
+    pub zombie: String,
+    #[serde(default)]
+    pub ice: TimeList,
+    pub time: i64,
+    #[serde(default)]
+    pub hugewave: Option<bool>,
+    #[serde(default)]
+    pub range_l: Option<i64>,
+    #[serde(default)]
+    pub range_r: Option<i64>,
+    #[serde(default)]
+    pub chill: ChillList,
+    #[serde(default)]
+    pub ice_source: Option<IceSource>,
+    #[serde(default)]
+    pub butter: TimeList,
+}
+
+/// 批量查询的结果，不填坐标范围时 `prob` 为空
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct BatchResult {
+    pub zombie: ZombieName,
+    pub ice: TimeList,
+    pub time: i64,
+    pub hugewave: bool,
+    pub range_l: Option<i64>,
+    pub range_r: Option<i64>,
+    pub chill: ChillList,
+    pub ice_source: IceSource,
+    pub butter: TimeList,
+    pub min: f64,
+    pub max: f64,
+    pub prob: Option<f64>,
+}
+
+/// 读取 CSV 格式的批量查询，表头为 `zombie,ice,time,hugewave,range_l,range_r,chill,ice_source,butter`
+///
+/// # Errors
+///
+/// 读取失败或某一行无法解析时返回 [`csv::Error`]。
+pub fn read_batch_csv<R: std::io::Read>(reader: R) -> Result<Vec<BatchQuery>, csv::Error> {
+    return csv::Reader::from_reader(reader).deserialize().collect();
+}
+
+/// 读取 JSON 格式的批量查询，格式为对象数组，字段与 CSV 表头相同
+///
+/// # Errors
+///
+/// 读取失败或格式有误时返回 [`serde_json::Error`]。
+pub fn read_batch_json<R: std::io::Read>(reader: R) -> Result<Vec<BatchQuery>, serde_json::Error> {
+    return serde_json::from_reader(reader);
+}
+
+/// 计算一个查询
+///
+/// # Errors
+///
+/// 僵尸不在数据中时返回 [`Error::UnknownZombie`]，坐标范围左边界大于右边界或只有右边界时返回 [`Error::InvalidRange`] 或
+/// [`Error::MissingRangeLeft`]，计算出错（如目标时间为负数）时返回 [`ZombieDb::calculate`] 的错误。
+pub fn run_query(db: &ZombieDb, query: &BatchQuery) -> Result<BatchResult, Error> {
+    let zombie = db.resolve(&query.zombie)?;
+    let hugewave = query.hugewave.unwrap_or(false);
+    let ice_source = query.ice_source.unwrap_or_default();
+    match (query.range_l, query.range_r) {
+        (Some(l), Some(r)) if l > r => return Err(Error::InvalidRange(l, r)),
+        (None, Some(r)) => return Err(Error::MissingRangeLeft(r)),
+        _ => {}
+    }
+    let d = db.calculate(&Scenario {
+        ice_times: query.ice.0.clone(),
+        hugewave: hugewave,
+        chills: query.chill.0.clone(),
+        ice_source: ice_source,
+        butter_times: query.butter.0.clone(),
+        ..Scenario::new(zombie.clone(), query.time)
+    })?;
+    // 与直接查询相同，坐标范围的概率接近 1 时视为 1
+    let dancecheat = matches!(db.get(&zombie)?.movement_type, MovementType::DanceCheat);
+    let prob = query.range_l.map(|l| match query.range_r {
+        Some(r) => d.prob_range_rounded(l, r, dancecheat),
+        None => d.prob(l),
+    });
+    return Ok(BatchResult {
+        zombie: zombie,
+        ice: query.ice.clone(),
+        time: query.time,
+        hugewave: hugewave,
+        range_l: query.range_l,
+        range_r: query.range_r,
+        chill: query.chill.clone(),
+        ice_source: ice_source,
+        butter: query.butter.clone(),
+        min: d.min,
+        max: d.max,
+        prob: prob,
+    });
+}
+
+/// 在 rayon 线程池上并行计算所有查询，结果顺序与输入相同，某个查询出错不影响其他查询
+pub fn run_batch(db: &ZombieDb, queries: &[BatchQuery]) -> Vec<Result<BatchResult, Error>> {
+    return queries.par_iter().map(|q| run_query(db, q)).collect();
+}
+
+/// 把查询结果写成 CSV，表头为查询的各个字段以及 `min,max,prob`
+///
+/// # Errors
+///
+/// 写入失败时返回 [`csv::Error`]。
+pub fn write_batch_csv<W: std::io::Write>(writer: W, results: &[BatchResult]) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    for result in results {
+        csv_writer.serialize(result)?;
+    }
+    csv_writer.flush()?;
+    return Ok(());
+}
@@ -1,55 +1,74 @@
 use crate::common::*;
+use crate::timeline::*;
+use crate::error::Error;
 use libm::erfc;
-use num_traits::ToPrimitive;
+use num_integer::Integer;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, ToPrimitive};
 use rayon::prelude::*;
 
-// 原速 out.0 cs + 减速 [out.1-200, out.1] cs + 原速 out.2 cs
-fn calc_time(data: &ZombieData, ice_time: i64, time: i64) -> (i64, i64, i64) {
-    let norm_time = ice_time - 1;
-    if ice_time == 0 || ice_time > time || data.chill_immune { (time, 0, 0) }
-    else if data.freeze_immune { (norm_time, min(time - norm_time, 1999), max(time - norm_time - 1999, 0)) }
-    else { (norm_time, max(min(time - norm_time, 1999) - 399, 0), max(time - norm_time - 1999, 0)) }
+// 匀速僵尸每 cs 的实际位移，减速时为原速的 0.4 倍
+pub(crate) fn constant_speed(speed: Num, state: MoveState) -> Option<Num> {
+    return match state {
+        MoveState::Normal => Some(speed.checked_mul(&Num::from(16384))?.round() / 16384),
+        MoveState::Chilled => Some(checked_product(&[speed, Num::new(2, 5), Num::from(16384)])?.round() / 16384),
+        MoveState::Stopped => Some(Num::new(0, 1)),
+    };
+}
+
+// a * n + b * m
+fn checked_dot(a: Num, n: i64, b: Num, m: i64) -> Option<Num> {
+    return a.checked_mul(&Num::from(n))?.checked_add(&b.checked_mul(&Num::from(m))?);
+}
+
+// contrib 为出生在 spawn.1 时的分布（已除以出生点个数），求出生点在 spawn 内均匀分布时的分布
+fn spread(contrib: &PosDistribution, spawn: (i64, i64), min: f64, max: f64) -> PosDistribution {
+    let spawn_span = (spawn.1 - spawn.0 + 1) as usize;
+    let len = contrib.dist.len();
+    // 结果的第 i 项为 contrib 的第 i - spawn_span + 1 到 i 项之和
+    let dist = (0..len + spawn_span - 1)
+        .map(|i| contrib.dist[i.saturating_sub(spawn_span - 1)..std::cmp::min(i + 1, len)].iter().sum())
+        .collect();
+    let mut result = PosDistribution { offset: contrib.offset - (spawn_span as i64 - 1), dist: dist, min: min, max: max };
+    result.trim();
+    return result;
 }
 
-fn calculate_constant(data: &ZombieData, ice_time: i64, time: i64) -> PosDistribution {
-    let speed_min_norm = (data.speed.0 * 16384).round() / 16384;
-    let speed_max_norm = (data.speed.1 * 16384).round() / 16384;
-    let speed_min_chill = (data.speed.0 * Num::new(2, 5) * 16384).round() / 16384;
-    let speed_max_chill = (data.speed.1 * Num::new(2, 5) * 16384).round() / 16384;
-    let mut contrib = [0.0; 880];
-    let (norm_time, chill_time_max, norm_time2) = calc_time(data, ice_time, time);
-    let chill_time_min = if data.freeze_immune {chill_time_max} else {max(chill_time_max - 200, 0)};
-    let minimum_chill_multiplier = 201 - (chill_time_max - chill_time_min);
-    let spawn_span = data.spawn.1 - data.spawn.0 + 1;
-    for chill_time in chill_time_min..=chill_time_max {
-        let weight = Num::new(if chill_time == chill_time_min {minimum_chill_multiplier} else {1}, 201) / spawn_span;
-        let dx_min = speed_min_norm * (norm_time + norm_time2) + speed_min_chill * chill_time;
-        let dx_max = speed_max_norm * (norm_time + norm_time2) + speed_max_chill * chill_time;
-        let pos_min = data.spawn.1 - dx_max.ceil().to_integer();
-        let pos_max = data.spawn.1 - dx_min.ceil().to_integer();
+fn calculate_constant(data: &ZombieData, spawn: (i64, i64), timelines: &[Timeline]) -> Option<PosDistribution> {
+    let speed_min_norm = constant_speed(data.speed.0, MoveState::Normal)?;
+    let speed_max_norm = constant_speed(data.speed.1, MoveState::Normal)?;
+    let speed_min_chill = constant_speed(data.speed.0, MoveState::Chilled)?;
+    let speed_max_chill = constant_speed(data.speed.1, MoveState::Chilled)?;
+    let mut contrib = PosDistribution::empty();
+    let mut global_dx_min = Num::from_integer(i64::MAX);
+    let mut global_dx_max = Num::new(0, 1);
+    let spawn_span = spawn.1 - spawn.0 + 1;
+    for timeline in timelines {
+        let weight = [timeline.weight, Num::new(1, spawn_span)];
+        let norm_time = timeline.count(MoveState::Normal);
+        let chill_time = timeline.count(MoveState::Chilled);
+        let dx_min = checked_dot(speed_min_norm, norm_time, speed_min_chill, chill_time)?;
+        let dx_max = checked_dot(speed_max_norm, norm_time, speed_max_chill, chill_time)?;
+        global_dx_min = min(global_dx_min, dx_min);
+        global_dx_max = max(global_dx_max, dx_max);
+        let pos_min = spawn.1 - dx_max.ceil().to_integer();
+        let pos_max = spawn.1 - dx_min.ceil().to_integer();
         if pos_min == pos_max {
-            contrib[pos_min as usize] += weight.to_f64().unwrap();
+            contrib.add(pos_min, product_f64(&weight));
         } else {
-            let l_ratio = (dx_max - dx_max.floor()) / (dx_max - dx_min);
-            let r_ratio = (dx_min.ceil() - dx_min) / (dx_max - dx_min);
-            contrib[pos_min as usize] += (weight * l_ratio).to_f64().unwrap();
-            contrib[pos_max as usize] += (weight * r_ratio).to_f64().unwrap();
+            let width = dx_max.checked_sub(&dx_min)?;
+            let l_ratio = dx_max.checked_sub(&dx_max.floor())?.checked_div(&width)?;
+            let r_ratio = dx_min.ceil().checked_sub(&dx_min)?.checked_div(&width)?;
+            let unit = Num::from(1).checked_div(&width)?;
+            contrib.add(pos_min, product_f64(&[weight[0], weight[1], l_ratio]));
+            contrib.add(pos_max, product_f64(&[weight[0], weight[1], r_ratio]));
             for i in (pos_min + 1)..pos_max {
-                contrib[i as usize] += (weight / (dx_max - dx_min)).to_f64().unwrap();
+                contrib.add(i, product_f64(&[weight[0], weight[1], unit]));
             }
         }
     }
-    let global_dx_min = speed_min_norm * norm_time + speed_min_chill * chill_time_min + speed_min_norm * norm_time2;
-    let global_dx_max = speed_max_norm * norm_time + speed_max_chill * chill_time_max + speed_max_norm * norm_time2;
-    let mut result = PosDistribution {
-        dist: [0.0; 880],
-        min: (Num::new(data.spawn.0, 1) - global_dx_max).to_f64().unwrap(),
-        max: (Num::new(data.spawn.1, 1) - global_dx_min).to_f64().unwrap()
-    };
-    for i in 0..880 {
-        result.dist[i] = contrib[i..min(i + spawn_span as usize, 880)].iter().sum();
-    }
-    return result;
+    return Some(spread(&contrib, spawn,
+                       Num::new(spawn.0, 1).checked_sub(&global_dx_max)?.to_f64().unwrap(),
+                       Num::new(spawn.1, 1).checked_sub(&global_dx_min)?.to_f64().unwrap()));
 }
 
 fn prob_between(l: f64, r: f64) -> f64 {
@@ -57,225 +76,462 @@ fn prob_between(l: f64, r: f64) -> f64 {
     else { (erfc(l / std::f64::consts::SQRT_2) - erfc(r / std::f64::consts::SQRT_2)) / 2.0 }
 }
 
-fn calculate_dancecheat(data: &ZombieData, ice_time: i64, time: i64) -> PosDistribution {
+fn calculate_dancecheat(data: &ZombieData, spawn: (i64, i64), timelines: &[Timeline]) -> PosDistribution {
     let k = data.speed.0.to_f64().unwrap();
-    let mut contrib = [0.0; 880];
-    let (norm_time, chill_time_max, norm_time2) = calc_time(data, ice_time, time);
-    let chill_time_min = if data.freeze_immune {chill_time_max} else {max(chill_time_max - 200, 0)};
-    let minimum_chill_multiplier = 201 - (chill_time_max - chill_time_min);
-    let spawn_span = data.spawn.1 - data.spawn.0 + 1;
-    for chill_time in chill_time_min..=chill_time_max {
-        let weight = (if chill_time == chill_time_min {minimum_chill_multiplier as f64} else {1.0}) / 201.0
-        / spawn_span as f64;
-        let norm_time = (norm_time + norm_time2) as f64;
-        let chill_time = chill_time as f64;
-        let mean = (data.spawn.1 as f64) - k * (norm_time + chill_time / 2.0);
+    let mut contrib = PosDistribution::empty();
+    let spawn_span = spawn.1 - spawn.0 + 1;
+    for timeline in timelines {
+        let weight = timeline.weight.to_f64().unwrap() / spawn_span as f64;
+        let norm_time = timeline.count(MoveState::Normal) as f64;
+        let chill_time = timeline.count(MoveState::Chilled) as f64;
+        let mean = (spawn.1 as f64) - k * (norm_time + chill_time / 2.0);
         let std = k * (49.0 / 2700.0 * (norm_time + chill_time / 4.0)).sqrt();
-        let pos_min = (mean - 10.0 * std) as usize;
-        let pos_max = (mean + 10.0 * std) as usize;
+        if std == 0.0 {
+            // 还没有移动过时位置是确定的
+            contrib.add(mean.floor() as i64, weight);
+            continue;
+        }
+        let pos_min = (mean - 10.0 * std).floor() as i64;
+        let pos_max = (mean + 10.0 * std).floor() as i64;
         for pos in pos_min..=pos_max {
             let l = (pos as f64 - mean) / std;
             let r = ((pos + 1) as f64 - mean) / std;
-            contrib[pos] += weight * prob_between(l, r);
+            contrib.add(pos, weight * prob_between(l, r));
         }
     }
-    let mut dist = [0.0; 880];
-    for i in 0..880 {
-        dist[i] = contrib[i..min(i + spawn_span as usize, 880)].iter().sum();
+    let mut result = spread(&contrib, spawn, 0.0, 0.0);
+    if let Some((first, last)) = result.support(1e-9) {
+        result.min = first as f64;
+        result.max = (last + 1) as f64 - 1.0 / 16384.0;
     }
-    return PosDistribution {
-        dist: dist,
-        min: dist.iter().position(|&x| x > 1e-9).unwrap() as f64,
-        max: 880.0 - 1.0 / 16384.0 - dist.iter().rev().position(|&x| x > 1e-9).unwrap() as f64
-    };
+    return result;
 }
 
-// 返回分母 <=n 且在 (l, r) 之间的所有分数，外加 l 和 r
-fn fraction_between(n: i64, l: Num, r: Num) -> Vec<Num> {
+// 返回分母 <=n 且在 (l, r) 之间的所有分数，外加 l 和 r。找到大于 l 的第一项后按 Farey 数列相邻项的递推依次得到后面各项
+fn fraction_between(n: i64, l: Num, r: Num) -> Option<Vec<Num>> {
     let mut result = vec![l];
-    for i in 1..=n {
-        let den_l = l * i;
-        let den_r = r * i;
-        let den_l = if den_l.is_integer() {den_l + 1} else {den_l.ceil()};
-        let den_r = if den_r.is_integer() {den_r - 1} else {den_r.floor()};
-        for j in den_l.to_integer()..=den_r.to_integer() {
-            if num_integer::gcd(i, j) == 1 {
-                result.push(Num::new(j, i));
-            }
+    let (l_num, l_den) = (i128::from(*l.numer()), i128::from(*l.denom()));
+    let (r_num, r_den) = (i128::from(*r.numer()), i128::from(*r.denom()));
+    // 大于 l 的最小分数，值相同时取分母最小的即为既约分数
+    let mut first: Option<(i128, i128)> = None;
+    for i in 1..=i128::from(n) {
+        let j = i128::from(i64::try_from((l_num * i).div_euclid(l_den) + 1).ok()?);
+        if first.is_none_or(|(num, den)| j * den < num * i) {
+            first = Some((j, i));
+        }
+    }
+    if let Some(mut cur) = first {
+        // 下一项 next 满足 cur.1 * next.0 - cur.0 * next.1 = 1，分母为不超过 n 的最大值
+        let den = (-(cur.0.rem_euclid(cur.1).extended_gcd(&cur.1).x)).rem_euclid(cur.1);
+        let den = den + (i128::from(n) - den) / cur.1 * cur.1;
+        let mut next = ((cur.0 * den + 1) / cur.1, den);
+        while cur.0 * r_den < r_num * cur.1 {
+            result.push(Num::new(i64::try_from(cur.0).ok()?, i64::try_from(cur.1).ok()?));
+            let k = (i128::from(n) + cur.1) / next.1;
+            (cur, next) = (next, (k * next.0 - cur.0, k * next.1 - cur.1));
         }
     }
     result.push(r);
-    result.sort_unstable();
-    return result;
+    return Some(result);
 }
 
-// arr[x0] + arr[x0 + k] + ... + arr[x0 + (n - 1) * k]
-fn total_shift(arr: &Vec<Num>, n: i64, k: Num, x0: Num) -> Num {
-    let n = Num::new(n, 1);
-    let mut result = Num::new(0, 1);
-    let first = x0.floor().to_integer();
-    let last = (x0 + (n - 1) * k).floor().to_integer();
-    let mut cur = Num::new(0, 1);
+// arr[floor(l * p0)] + arr[floor(l * (p0 + step))] + ... 共 n 项，下标对 arr 的长度取模。相位均为 l 的整数倍，
+// 只做整数运算，每一帧只计算一次
+fn total_shift(arr: &[i64], n: i64, l: Num, step: i64, p0: i64) -> Option<i64> {
+    let (num, den) = (*l.numer(), *l.denom());
+    let frame = |p: i64| num.checked_mul(p).map(|x| x.div_euclid(den));
+    let value = |i: i64| arr[i.rem_euclid(arr.len() as i64) as usize];
+    let first = frame(p0)?;
+    let last = frame(p0.checked_add(step.checked_mul(n - 1)?)?)?;
+    let stride = num.checked_mul(step)?;
+    let mut result: i64 = 0;
+    let mut cur = 0;
     for i in first..last {
-        let next = ((Num::new(i + 1, 1) - x0) / k).ceil();
-        result += arr[i as usize % arr.len()] * (next - cur);
+        // 相位第一次不小于 i + 1 的项，此时 stride > 0 且被除数为正
+        let next = ((i + 1).checked_mul(den)?.checked_sub(num.checked_mul(p0)?)? + stride - 1) / stride;
+        result = result.checked_add(value(i).checked_mul(next - cur)?)?;
         cur = next;
     }
-    return result + arr[last as usize % arr.len()] * (n - cur);
+    return result.checked_add(value(last).checked_mul(n - cur)?);
 }
 
-fn calculate_animation(data: &ZombieData, ice_time: i64, time: i64, animation: Option<&Vec<Num>>) -> PosDistribution {
-    let animation = animation.unwrap_or_else(|| match &data.movement_type {
-        MovementType::Animation(x) | MovementType::Dancing(x) => x,
-        _ => unreachable!()
-    });
+// 动画速度 k 的范围，k 是减速状态下相位的变化率
+pub(crate) fn k_range(data: &ZombieData, animation: &[Num]) -> Option<(Num, Num)> {
     let anim_len = animation.len() as i64;
-    let total: Num = animation.iter().sum();
-    let speed_scale_factor = Num::new(47, 100) * anim_len / total;
-    let dis_scale_factor = Num::new(anim_len + 1, anim_len);
-    let (norm_time, chill_time_max, norm_time2) = calc_time(data, ice_time, time);
-    let n = norm_time * 2 + chill_time_max;
-    // k 是减速状态下相位的变化率
-    let k_min = data.speed.0 * speed_scale_factor / 2;
-    let k_max = data.speed.1 * speed_scale_factor / 2;
-    // k 在 [k_segments[i], k_segments[i+1]) 范围内变化时 dx 正比于 k
-    let k_segments =
-        if chill_time_max != 0 { fraction_between(n, k_min, k_max) }
-        else { fraction_between(n / 2, k_min * 2, k_max * 2).iter().map(|x| x / 2).collect() };
-    // 举例：chill_time_max = 100 时，实际减速时间取到最小值 0 的概率是 101/201 (冰 500-600cs)，是取到其他数值的 101 倍
-    let minimum_chill_multiplier = max(200 - chill_time_max + 1, 1);
-    let (contrib, dx_global_min, dx_global_max) = k_segments
-        .par_windows(2)
-        .map(|lr| {
-        let (l, r) = (lr[0], lr[1]);
-        let mut contrib = [0.0; 880];
-        let mut dx_global_min = Num::new(1000, 1);
-        let mut dx_global_max = Num::new(0, 1);
-        let mut shift_min = Num::new(0, 1);
-        let mut shift_max = Num::new(0, 1);
-        let mut phase = l * 2;
-        let shift_norm_l: Vec<_> = animation.iter().map(|x| (x * dis_scale_factor * l * 32768).round() / 16384).collect();
-        let shift_norm_r: Vec<_> = animation.iter().map(|x| (x * dis_scale_factor * r * 32768).round() / 16384).collect();
-        let shift_l: Vec<_> = animation.iter().map(|x| (x * dis_scale_factor * l * 16384).round() / 16384).collect();
-        let shift_r: Vec<_> = animation.iter().map(|x| (x * dis_scale_factor * r * 16384).round() / 16384).collect();
-        shift_min += total_shift(&shift_norm_l, norm_time, l * 2, phase);
-        shift_max += total_shift(&shift_norm_r, norm_time, l * 2, phase);
-        phase += l * 2 * norm_time;
-        let chill_time_base = max(chill_time_max - 200, 0);
-        shift_min += total_shift(&shift_l, chill_time_base, l, phase);
-        shift_max += total_shift(&shift_r, chill_time_base, l, phase);
-        phase += l * chill_time_base;
-        for i in 0..=min(chill_time_max, 200) {
-            let dx_min = shift_min + total_shift(&shift_norm_l, norm_time2, l * 2, phase);
-            let dx_max = shift_max + total_shift(&shift_norm_r, norm_time2, l * 2, phase);
-            // 逐个更新不同冻结时间的期望
-            dx_global_min = min(dx_global_min, dx_min);
-            dx_global_max = max(dx_global_max, dx_max);
-            let spawn_span = data.spawn.1 - data.spawn.0 + 1;
-            let weight = Num::new(if i == 0 {minimum_chill_multiplier} else {1}, 201) / spawn_span
-                * (if k_min == k_max {Num::new(1, 1)} else {(r - l) / (k_max - k_min)}); // avoid 0/0
-            let dx_l = dx_min.ceil().to_integer();
-            let dx_r = dx_max.ceil().to_integer();
-            for dx in dx_l..=dx_r {
-                let ratio =
-                    if dx_min == dx_max {Num::new(1, 1)}
-                    else {
-                        let ratio_l = (max(Num::new(dx - 1, 1), dx_min) - dx_min) / (dx_max - dx_min);
-                        let ratio_r = (min(Num::new(dx, 1), dx_max) - dx_min) / (dx_max - dx_min);
-                        ratio_r - ratio_l
-                    };
-                contrib[(data.spawn.1 - dx) as usize] += (weight * ratio).to_f64().unwrap();
+    let total = animation.iter().try_fold(Num::new(0, 1), |sum, x| sum.checked_add(x))?;
+    let speed_scale_factor = checked_product(&[Num::new(47, 100), Num::from(anim_len)])?.checked_div(&total)?;
+    let k = |speed: Num| checked_product(&[speed, speed_scale_factor, Num::new(1, 2)]);
+    return Some((k(data.speed.0)?, k(data.speed.1)?));
+}
+
+// 相位总变化量最多为 n * k，以及是否有减速，二者决定 k 的分段；n 超出 i64 的范围时为 None
+fn phase_bound<'a>(timelines: impl IntoIterator<Item = &'a Timeline>) -> Option<(i64, bool)> {
+    return timelines.into_iter()
+        .map(|x| Some((x.count(MoveState::Normal).checked_mul(2)?.checked_add(x.count(MoveState::Chilled))?, x.count(MoveState::Chilled) != 0)))
+        .try_fold((0, false), |(n, chilled), x| x.map(|x| (max(n, x.0), chilled || x.1)));
+}
+
+// k 在 [k_segments[i], k_segments[i+1]) 范围内变化时 dx 正比于 k
+pub(crate) fn k_segments<'a>(data: &ZombieData, timelines: impl IntoIterator<Item = &'a Timeline>,
+                             animation: &[Num]) -> Option<Vec<Num>> {
+    let (k_min, k_max) = k_range(data, animation)?;
+    let (n, chilled) = phase_bound(timelines)?;
+    let two = Num::from(2);
+    return if chilled { fraction_between(n, k_min, k_max) }
+        else { fraction_between(n / 2, k_min.checked_mul(&two)?, k_max.checked_mul(&two)?)?.iter().map(|x| x.checked_div(&two)).collect() };
+}
+
+// 相位变化量上界的分段方式：减速时按分母不超过 n 的分数分段，否则 k 放大 2 倍后按分母不超过 n / 2 的分数分段，
+// 返回 (是否减速, 分母上界)
+fn partition_order(bound: (i64, bool)) -> (bool, i64) {
+    return if bound.1 { (true, bound.0) } else { (false, bound.0 / 2) };
+}
+
+// k 的一段 [l, r)
+type Segment = (Num, Num);
+
+// 对 orders（升序）中的每个 order，分母不超过 order 且在 (l, r) 之间的分数把 [l, r] 分成若干段。
+// 返回所有出现过的段，以及使用这一段的 order 的范围（闭区间）。内部的段是 Farey 数列的相邻两项 a/b、c/d，
+// 从较大的分母起相邻，直到分母为 b + d 的中间项出现，因此由每个分数和它的两个父节点即可得到，不必对每个 order 列出所有分数
+fn farey_segments(orders: &[i64], l: Num, r: Num) -> Option<Vec<(Segment, (i64, i64))>> {
+    let used = |lo: i64, hi: i64| orders.partition_point(|&x| x < lo) < orders.partition_point(|&x| x <= hi);
+    let points = fraction_between(*orders.last()?, l, r)?;
+    let inner = &points[1..points.len() - 1];
+    let mut result = Vec::new();
+    for &z in inner {
+        let (num, den) = (*z.numer(), *z.denom());
+        // 左父节点 p/q 满足 q * num - p * den = 1 且 q <= den，右父节点为 (num - p)/(den - q)
+        let q = if den == 1 {1} else {num.rem_euclid(den).extended_gcd(&den).x.rem_euclid(den)};
+        let p = q.checked_mul(num)?.checked_sub(1)? / den;
+        let left = Num::new(p, q);
+        if left > l && used(den, den + q - 1) {
+            result.push(((left, z), (den, den + q - 1)));
+        }
+        if den > q {
+            let right = Num::new(num - p, den - q);
+            if right < r && used(den, 2 * den - q - 1) {
+                result.push(((z, right), (den, 2 * den - q - 1)));
             }
-            shift_min += shift_l[phase.to_integer() as usize % animation.len()];
-            shift_max += shift_r[phase.to_integer() as usize % animation.len()];
-            phase += l;
         }
-        (contrib, dx_global_min, dx_global_max)
-    }).reduce(|| { ([0.0; 880], Num::new(1000, 1), Num::new(0, 1)) },
-        |(contrib, dx_global_min, dx_global_max), (contrib_, dx_global_min_, dx_global_max_)| {
-        let mut contrib = contrib;
-        for i in 0..880 {
-            contrib[i] += contrib_[i];
+    }
+    // 与 l 相邻的一项是分母不超过 order 的最小分数，分母小于之前所有分数时在之前的分母出现前一直是这一项，与 r 相邻的一项同理
+    let mut min_den = i64::MAX;
+    for &z in inner {
+        if *z.denom() < min_den {
+            if used(*z.denom(), min_den - 1) {
+                result.push(((l, z), (*z.denom(), min_den - 1)));
+            }
+            min_den = *z.denom();
         }
-        (contrib, min(dx_global_min, dx_global_min_), max(dx_global_max, dx_global_max_))
-    });
-    let mut result = PosDistribution {
-        dist: [0.0; 880],
-        min: (Num::new(data.spawn.0, 1) - dx_global_max).to_f64().unwrap(),
-        max: (Num::new(data.spawn.1, 1) - dx_global_min).to_f64().unwrap(),
-    };
-    let spawn_span = (data.spawn.1 - data.spawn.0 + 1) as usize;
-    for i in 0..880 {
-        result.dist[i] = contrib[i..min(i + spawn_span, 880)].iter().sum();
     }
-    return result;
+    let mut min_den = i64::MAX;
+    for &z in inner.iter().rev() {
+        if *z.denom() < min_den {
+            if used(*z.denom(), min_den - 1) {
+                result.push(((z, r), (*z.denom(), min_den - 1)));
+            }
+            min_den = *z.denom();
+        }
+    }
+    // 分母不超过 order 的分数都不在 (l, r) 内时只有一段
+    if used(i64::MIN, min_den - 1) {
+        result.push(((l, r), (i64::MIN, min_den - 1)));
+    }
+    return Some(result);
 }
 
-fn calculate_regular(data: &ZombieData, ice_time: i64, time: i64) -> PosDistribution {
-    let MovementType::Regular(anim_a, anim_b) = &data.movement_type else {
-        unreachable!();
-    };
-    let dist_a = calculate_animation(data, ice_time, time, Some(anim_a));
-    let dist_b = calculate_animation(data, ice_time, time, Some(anim_b));
-    let mut result = PosDistribution {
-        dist: [0.0; 880],
-        min: f64::min(dist_a.min, dist_b.min),
-        max: f64::max(dist_a.max, dist_b.max)
-    };
-    for i in 0..880 {
-        result.dist[i] = (dist_a.dist[i] + dist_b.dist[i]) / 2.0;
+// k 在 [l, r) 内时每一帧的位移，分别对应 k = l 和 k = r，以 1/16384 为单位。相位总是 l 的整数倍，
+// 记录这一倍数，原速时每 cs 增加 2，减速时增加 1
+pub(crate) struct Shifts {
+    l: Num,
+    norm_l: Vec<i64>,
+    norm_r: Vec<i64>,
+    chill_l: Vec<i64>,
+    chill_r: Vec<i64>,
+}
+
+impl Shifts {
+    pub(crate) fn new(animation: &[Num], l: Num, r: Num) -> Option<Shifts> {
+        let anim_len = animation.len() as i64;
+        let dis_scale_factor = Num::new(anim_len + 1, anim_len);
+        let base = animation.iter().map(|x| x.checked_mul(&dis_scale_factor)).collect::<Option<Vec<Num>>>()?;
+        // round(x * k * scale)，与 Ratio::round 相同，.5 时远离 0
+        let shift = |k: Num, scale: i64| -> Option<Vec<i64>> {
+            base.iter().map(|x| {
+                let num = i128::from(*x.numer()).checked_mul(i128::from(*k.numer()))?.checked_mul(i128::from(scale))?;
+                let den = i128::from(*x.denom()).checked_mul(i128::from(*k.denom()))?;
+                let (quot, rem) = (num / den, num % den);
+                let round = if 2 * rem.abs() >= den { quot + num.signum() } else { quot };
+                return i64::try_from(round).ok();
+            }).collect()
+        };
+        return Some(Shifts {
+            l: l,
+            norm_l: shift(l, 32768)?,
+            norm_r: shift(r, 32768)?,
+            chill_l: shift(l, 16384)?,
+            chill_r: shift(r, 16384)?,
+        });
+    }
+
+    // 初始的 (dx_min, dx_max, phase)，第 1 cs 的相位为 2l
+    pub(crate) fn start() -> (i64, i64, i64) {
+        return (0, 0, 2);
     }
-    return result
+
+    // 以 state 状态移动 len cs 后的 (dx_min, dx_max, phase)
+    pub(crate) fn advance(&self, progress: (i64, i64, i64), state: MoveState, len: i64) -> Option<(i64, i64, i64)> {
+        let (dx_min, dx_max, phase) = progress;
+        let (step, l, r) = if state == MoveState::Normal { (2, &self.norm_l, &self.norm_r) }
+            else { (1, &self.chill_l, &self.chill_r) };
+        return Some((dx_min.checked_add(total_shift(l, len, self.l, step, phase)?)?,
+                     dx_max.checked_add(total_shift(r, len, self.l, step, phase)?)?,
+                     phase.checked_add(step.checked_mul(len)?)?));
+    }
+
+    // 位移的最小、最大值
+    pub(crate) fn dx(progress: (i64, i64, i64)) -> (Num, Num) {
+        return (Num::new(progress.0, 16384), Num::new(progress.1, 16384));
+    }
+}
+
+// 各个目标时间的结果中累加的部分，dx 为位移的最小、最大值，以 1/16384 为单位
+struct Accum {
+    contrib: Vec<PosDistribution>,
+    dx: Vec<Option<(i64, i64)>>,
 }
 
-fn calculate_dancing(data: &ZombieData, ice_time: i64, time: i64) -> PosDistribution {
-    let (norm_time, _, _) = calc_time(data, ice_time, time);
-    if norm_time < 299 {
-        return calculate_animation(data, 0, norm_time, None);
+impl Accum {
+    fn new(len: usize) -> Accum {
+        return Accum { contrib: vec![PosDistribution::empty(); len], dx: vec![None; len] };
     }
-    let maximum_norm_multiplier = max(310 - norm_time + 1, 1);
-    let mut result = PosDistribution {
-        dist: [0.0; 880],
-        min: 1000.0,
-        max: 0.0
-    };
-    for norm in 299..=min(norm_time, 310) {
-        let d = calculate_animation(data, 0, norm, None);
+
+    fn merge(mut self, other: Accum) -> Accum {
+        for (contrib, other) in self.contrib.iter_mut().zip(&other.contrib) {
+            for (i, &p) in other.dist.iter().enumerate() {
+                contrib.add(other.offset + i as i64, p);
+            }
+        }
+        for (dx, other) in self.dx.iter_mut().zip(other.dx) {
+            *dx = match (*dx, other) {
+                (Some(a), Some(b)) => Some((min(a.0, b.0), max(a.1, b.1))),
+                (a, b) => a.or(b),
+            };
+        }
+        return self;
+    }
+}
+
+// series[i] 为第 i 个目标时间的所有情况。目标时间越晚 k 的分段越细，较早时间的分段点都包含在较晚时间的分段中，
+// 每个 k 段只计算一次，使用这一段的各个目标时间共用相同前缀的位移计算，结果与单独计算每个目标时间相同
+fn calculate_animation(data: &ZombieData, spawn: (i64, i64), series: &[Vec<Timeline>], animation: &[Num]) -> Option<Vec<PosDistribution>> {
+    let (k_min, k_max) = k_range(data, animation)?;
+    // 每个 k 段以及分段中包含这一段的 (是否减速, 分母上界) 的范围
+    let orders: Vec<(bool, i64)> = series.iter().map(|x| phase_bound(x).map(partition_order)).collect::<Option<_>>()?;
+    let mut segments: HashMap<Segment, Vec<(bool, i64, i64)>> = HashMap::new();
+    for chilled in [false, true] {
+        let mut family: Vec<i64> = orders.iter().filter(|x| x.0 == chilled).map(|x| x.1).collect();
+        if family.is_empty() {
+            continue;
+        }
+        family.sort_unstable();
+        family.dedup();
+        let scale = Num::from(if chilled {1} else {2});
+        for ((l, r), (lo, hi)) in farey_segments(&family, k_min.checked_mul(&scale)?, k_max.checked_mul(&scale)?)? {
+            segments.entry((l.checked_div(&scale)?, r.checked_div(&scale)?)).or_default().push((chilled, lo, hi));
+        }
+    }
+    let mut segments: Vec<_> = segments.into_iter().collect();
+    segments.sort_unstable_by_key(|x| x.0);
+    // 按分段方式排列的各个目标时间，使用某一段的目标时间是其中连续的一段
+    let mut targets_by_order: Vec<((bool, i64), usize)> = orders.iter().copied().zip(0..).collect();
+    targets_by_order.sort_unstable();
+    let spawn_span = spawn.1 - spawn.0 + 1;
+    // 去掉冻结段后相同的情况合并计算，记录各自所属的目标时间和权重
+    let mut merged = HashMap::new();
+    for (i, timelines) in series.iter().enumerate() {
+        for timeline in timelines {
+            merged.entry(timeline.moving_runs()).or_insert_with(Vec::new).push((i, timeline.weight));
+        }
+    }
+    let mut moving: Vec<_> = merged.into_iter().collect();
+    moving.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    // entries[i] 为第 i 个目标时间用到的情况，每个 k 段只看使用它的目标时间的情况，不必扫描所有情况
+    let mut entries = vec![Vec::new(); series.len()];
+    for (j, (_, targets)) in moving.iter().enumerate() {
+        for &(i, _) in targets {
+            entries[i].push(j);
+        }
+    }
+    let accum = segments
+        .par_iter()
+        .try_fold(|| Accum::new(series.len()), |mut accum, &((l, r), ref ranges)| {
+        let uses = |i: usize| ranges.iter().any(|&(chilled, lo, hi)| orders[i].0 == chilled && lo <= orders[i].1 && orders[i].1 <= hi);
+        let mut indices: Vec<usize> = ranges.iter().flat_map(|&(chilled, lo, hi)| {
+            let start = targets_by_order.partition_point(|x| x.0 < (chilled, lo));
+            let end = targets_by_order.partition_point(|x| x.0 <= (chilled, hi));
+            targets_by_order[start..end].iter().flat_map(|&(_, i)| entries[i].iter().copied())
+        }).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        let shifts = Shifts::new(animation, l, r)?;
+        let segment_weight = if k_min == k_max {Num::new(1, 1)} else {r.checked_sub(&l)?.checked_div(&k_max.checked_sub(&k_min)?)?}; // avoid 0/0
+        let unit = Num::new(1, spawn_span).checked_mul(&segment_weight);
+        // progress[i] 为上一种情况经过前 i 段后的 (dx_min, dx_max, phase)
+        let mut progress = vec![Shifts::start()];
+        let mut prev_runs: &[(MoveState, i64)] = &[];
+        for (runs, targets) in indices.iter().map(|&j| &moving[j]) {
+            // 与上一种情况相同的前缀直接复用，第一个不同的段如果只是更长则从上一种情况接着算
+            let common = prev_runs.iter().zip(runs.iter()).take_while(|(a, b)| a == b).count();
+            let extend = match (prev_runs.get(common), runs.get(common)) {
+                (Some(&(s0, len0)), Some(&(s1, len1))) if s0 == s1 && len0 <= len1 => Some(progress[common + 1]),
+                _ => None,
+            };
+            progress.truncate(common + 1);
+            for (i, &(state, len)) in runs.iter().enumerate().skip(common) {
+                let next = match extend {
+                    Some(prev) if i == common => shifts.advance(prev, state, len - prev_runs[i].1)?,
+                    _ => shifts.advance(progress[i], state, len)?,
+                };
+                progress.push(next);
+            }
+            prev_runs = runs;
+            // 以 1/16384 为单位
+            let (dx_min, dx_max, _) = progress[runs.len()];
+            let dx_l = -(-dx_min).div_euclid(16384);
+            let dx_r = -(-dx_max).div_euclid(16384);
+            for &(target, weight) in targets.iter().filter(|x| uses(x.0)) {
+                let dx = &mut accum.dx[target];
+                *dx = Some(dx.map_or((dx_min, dx_max), |(a, b)| (min(a, dx_min), max(b, dx_max))));
+                // 权重的乘积不超出 Num 的范围时预先算出
+                let weight = [weight, Num::new(1, spawn_span), segment_weight];
+                let exact = unit.and_then(|x| weight[0].checked_mul(&x));
+                for dx in dx_l..=dx_r {
+                    // [dx - 1, dx] 与 [dx_min, dx_max] 的交集占后者的比例
+                    let ratio =
+                        if dx_min == dx_max {Num::new(1, 1)}
+                        else {Num::new(min(dx * 16384, dx_max) - max((dx - 1) * 16384, dx_min), dx_max - dx_min)};
+                    let p = match exact {
+                        Some(w) => product_f64(&[w, ratio]),
+                        None => product_f64(&[weight[0], weight[1], weight[2], ratio]),
+                    };
+                    accum.contrib[target].add(spawn.1 - dx, p);
+                }
+            }
+        }
+        Some(accum)
+    }).try_reduce(|| Accum::new(series.len()), |a, b| Some(a.merge(b)))?;
+    return accum.contrib.iter().zip(accum.dx).map(|(contrib, dx)| {
+        let (dx_min, dx_max) = dx.unwrap_or_default();
+        let (dx_min, dx_max) = (Num::new(dx_min, 16384), Num::new(dx_max, 16384));
+        Some(spread(contrib, spawn,
+                    Num::new(spawn.0, 1).checked_sub(&dx_max)?.to_f64().unwrap(),
+                    Num::new(spawn.1, 1).checked_sub(&dx_min)?.to_f64().unwrap()))
+    }).collect();
+}
+
+fn calculate_regular(data: &ZombieData, spawn: (i64, i64), series: &[Vec<Timeline>],
+                     anim_a: &[Num], anim_b: &[Num]) -> Option<Vec<PosDistribution>> {
+    let dist_a = calculate_animation(data, spawn, series, anim_a)?;
+    let dist_b = calculate_animation(data, spawn, series, anim_b)?;
+    return Some(dist_a.iter().zip(&dist_b).map(|(a, b)| {
+        let offset = min(a.offset, b.offset);
+        let len = max(a.offset + a.dist.len() as i64, b.offset + b.dist.len() as i64) - offset;
+        PosDistribution {
+            offset: offset,
+            dist: (offset..offset + len).map(|x| f64::midpoint(a.prob(x), b.prob(x))).collect(),
+            min: f64::min(a.min, b.min),
+            max: f64::max(a.max, b.max),
+        }
+    }).collect());
+}
+
+// 舞王只在进场阶段移动，进场在 299-310cs 间结束，减速时照常进场，被黄油时暂停，被冰后不再移动
+fn calculate_dancing(data: &ZombieData, spawn: (i64, i64), timelines: &[Timeline], ice: Option<i64>,
+                     animation: &[Num]) -> Option<PosDistribution> {
+    // 进场结束前已被冰或已到目标时间的情况相同，合并后记录个数
+    let mut walks: Vec<(Timeline, i64)> = Vec::new();
+    for walk in 299..=310 {
+        let timeline = timelines[0].dancing_walk(walk, ice);
+        match walks.last_mut() {
+            Some(last) if last.0.runs == timeline.runs => last.1 += 1,
+            _ => walks.push((timeline, 1)),
+        }
+    }
+    if walks.len() == 1 {
+        return Some(calculate_animation(data, spawn, &[vec![walks.remove(0).0]], animation)?.remove(0));
+    }
+    let series: Vec<Vec<Timeline>> = walks.iter().map(|x| vec![x.0.clone()]).collect();
+    let mut result = PosDistribution::empty();
+    for ((_, multiplier), d) in walks.iter().zip(calculate_animation(data, spawn, &series, animation)?) {
         result.min = f64::min(result.min, d.min);
         result.max = f64::max(result.max, d.max);
-        let multiplier = if norm == min(norm_time, 310) {maximum_norm_multiplier} else {1};
-        for i in 0..880 {
-            result.dist[i] += d.dist[i] * multiplier as f64 / 12.0;
+        for (i, &p) in d.dist.iter().enumerate() {
+            result.add(d.offset + i as i64, p * *multiplier as f64 / 12.0);
         }
     }
-    return result
+    result.trim();
+    return Some(result);
 }
 
-fn calculate_zomboni(data: &ZombieData, _ice_time: i64, time: i64) -> PosDistribution {
-    let mut result = PosDistribution {
-        dist: [0.0; 880],
-        min: 1000.0,
-        max: 0.0
-    };
-    for spawn in data.spawn.0..=data.spawn.1 {
-        let mut pos = spawn as f64;
-        for _ in 0..time {
-            pos -= ((pos - 700.0).floor() / 2000.0 + 0.25).clamp(0.1, 0.25);
+// 冰车移动 time cs 后的坐标，越接近 700 越慢
+pub(crate) fn zomboni_pos(spawn_x: i64, time: i64) -> f64 {
+    let mut pos = spawn_x as f64;
+    for _ in 0..time {
+        pos -= ((pos - 700.0).floor() / 2000.0 + 0.25).clamp(0.1, 0.25);
+    }
+    return pos;
+}
+
+// 冰车不受冰和减速影响，只会被黄油停住
+fn calculate_zomboni(spawn: (i64, i64), timelines: &[Timeline]) -> PosDistribution {
+    let mut result = PosDistribution::empty();
+    for timeline in timelines {
+        let time = timeline.count(MoveState::Normal) + timeline.count(MoveState::Chilled);
+        let weight = timeline.weight.to_f64().unwrap();
+        for spawn_x in spawn.0..=spawn.1 {
+            let pos = zomboni_pos(spawn_x, time);
+            result.add(pos.floor() as i64, weight / (spawn.1 - spawn.0 + 1) as f64);
+            result.min = f64::min(result.min, pos);
+            result.max = f64::max(result.max, pos);
         }
-        result.dist[pos as usize] += 1.0 / (data.spawn.1 - data.spawn.0 + 1) as f64;
-        result.min = f64::min(result.min, pos);
-        result.max = f64::max(result.max, pos);
     }
+    result.trim();
     return result;
 }
 
-pub fn calculate_pos_distribution(data: &ZombieData, ice_time: i64, time: i64) -> PosDistribution {
-    match data.movement_type {
-        MovementType::Constant => calculate_constant(data, ice_time, time),
-        MovementType::Animation(_) => calculate_animation(data, ice_time, time, None),
-        MovementType::Regular(_, _) => calculate_regular(data, ice_time, time),
-        MovementType::DanceCheat => calculate_dancecheat(data, ice_time, time),
-        MovementType::Dancing(_) => calculate_dancing(data, ice_time, time),
-        MovementType::Zomboni => calculate_zomboni(data, ice_time, time),
-    }
+/// 计算 `scenario.time` 时的分布
+///
+/// # Errors
+///
+/// 精确计算的中间结果超出 [`Num`] 的范围时返回 [`Error::Overflow`]，见 [`calculate_series`]。
+pub fn calculate_pos_distribution(data: &ZombieData, scenario: &Scenario) -> Result<PosDistribution, Error> {
+    return Ok(calculate_series(data, scenario, &[scenario.time])?.remove(0));
+}
+
+/// 依次计算 `times` 中每个目标时间的分布，忽略 `scenario.time`
+///
+/// 结果与逐个调用 [`calculate_pos_distribution`] 相同；相同的动画速度分段只计算一次，其中各个时间共用相同前缀的位移计算，各个分段并行计算。
+///
+/// # Errors
+///
+/// 位移和动画相位的精确计算溢出时返回 [`Error::Overflow`]，不会得到错误的结果；只用于求概率的权重之积溢出时改用 `BigRational` 计算。
+pub fn calculate_series(data: &ZombieData, scenario: &Scenario, times: &[i64]) -> Result<Vec<PosDistribution>, Error> {
+    let spawn = if scenario.hugewave {data.spawn_hugewave} else {data.spawn};
+    let series: Vec<Vec<Timeline>> = times.iter()
+        .map(|&time| build_timelines(data, &Scenario { time: time, ..scenario.clone() }))
+        .collect::<Option<_>>()
+        .ok_or(Error::Overflow)?;
+    let result = match &data.movement_type {
+        MovementType::Constant => series.iter().map(|x| calculate_constant(data, spawn, x)).collect(),
+        MovementType::Animation(x) => calculate_animation(data, spawn, &series, x),
+        MovementType::Regular(a, b) => calculate_regular(data, spawn, &series, a, b),
+        MovementType::DanceCheat => Some(series.iter().map(|x| calculate_dancecheat(data, spawn, x)).collect()),
+        MovementType::Dancing(x) => {
+            let ice = first_freeze(data, scenario);
+            series.iter().map(|t| calculate_dancing(data, spawn, t, ice, x)).collect()
+        },
+        MovementType::Zomboni => Some(series.iter().map(|x| calculate_zomboni(spawn, x)).collect()),
+    };
+    return result.ok_or(Error::Overflow);
 }
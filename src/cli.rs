@@ -0,0 +1,464 @@
+use crate::commands::*;
+use zombie_movement_calculator::common::*;
+use zombie_movement_calculator::explosion::{COLS, CENTER_XS};
+use zombie_movement_calculator::{table, wave, Error, Explosion, PosEvent, Scene, Strike, ZombieDb, BUILTIN_DATA};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::process::ExitCode;
+
+/// 僵尸坐标分布计算器。不带参数运行时进入交互模式。
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// 僵尸类型，如 giga、PoleVaulting
+    #[arg(short, long, requires = "time")]
+    zombie: Option<String>,
+    /// 目标时间
+    #[arg(short, long, requires = "zombie")]
+    time: Option<i64>,
+    #[command(flatten)]
+    events: EventArgs,
+    /// 关注的坐标范围，可填单个坐标或左右边界
+    #[arg(short, long, num_args = 1..=2, value_names = ["L", "R"], allow_negative_numbers = true, requires = "zombie")]
+    range: Vec<i64>,
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    format: OutputFormat,
+    /// 僵尸数据文件，格式与 assets/data.csv 相同，不填时使用内置数据
+    #[arg(long, value_name = "FILE", global = true)]
+    data: Option<std::path::PathBuf>,
+}
+
+// 一只僵尸的查询参数，目标时间由子命令决定
+#[derive(clap::Args)]
+struct ZombieArgs {
+    /// 僵尸类型，如 giga、PoleVaulting
+    #[arg(short, long)]
+    zombie: String,
+    #[command(flatten)]
+    events: EventArgs,
+}
+
+impl ZombieArgs {
+    fn scenario(&self, db: &ZombieDb, time: i64) -> Result<Scenario, Error> {
+        return Ok(self.events.scenario(db.resolve(&self.zombie)?, time));
+    }
+}
+
+#[derive(clap::Args)]
+struct ScenarioArgs {
+    #[command(flatten)]
+    zombie: ZombieArgs,
+    /// 目标时间
+    #[arg(short, long)]
+    time: i64,
+}
+
+impl ScenarioArgs {
+    fn scenario(&self, db: &ZombieDb) -> Result<Scenario, Error> {
+        return self.zombie.scenario(db, self.time);
+    }
+}
+
+// 除僵尸类型和目标时间外的查询参数
+#[derive(clap::Args)]
+pub(crate) struct EventArgs {
+    /// 冰时机，可以填多次，不用冰则不填
+    #[arg(short, long, allow_negative_numbers = true)]
+    ice: Vec<i64>,
+    /// 冰时机的含义：effective（生效时间，默认）、regular（白天寒冰菇种下时间）、imitater（模仿者种下时间）、ice3（咖啡豆种下时间）
+    #[arg(long, default_value_t = IceSource::Effective)]
+    ice_source: IceSource,
+    /// 旗帜波（大波）出生
+    #[arg(short = 'w', long)]
+    hugewave: bool,
+    /// 减速命中（寒冰射手、冰瓜），格式为 START[:INTERVAL[:END]]，可以填多次
+    #[arg(long, value_name = "START[:INTERVAL[:END]]")]
+    chill: Vec<Chill>,
+    /// 黄油命中时间，可以填多次
+    #[arg(short, long)]
+    butter: Vec<i64>,
+}
+
+impl EventArgs {
+    // 是否没有填写任何参数
+    fn is_empty(&self) -> bool {
+        return self.ice.is_empty() && self.ice_source == IceSource::Effective && !self.hugewave
+            && self.chill.is_empty() && self.butter.is_empty();
+    }
+
+    pub(crate) fn scenario(&self, zombie: ZombieName, time: i64) -> Scenario {
+        return Scenario {
+            ice_times: self.ice.clone(),
+            ice_source: self.ice_source,
+            hugewave: self.hugewave,
+            chills: self.chill.clone(),
+            butter_times: self.butter.clone(),
+            ..Scenario::new(zombie, time)
+        };
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub(crate) enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Query(QueryCommand),
+    /// 检查僵尸数据（--data 指定的文件，不填则为内置数据）是否有误，有错误时返回非零状态码
+    Validate,
+}
+
+// 需要读取僵尸数据的子命令
+#[derive(Subcommand)]
+enum QueryCommand {
+    /// 批量计算文件中的查询（CSV 或 JSON），每个查询输出一行 CSV 结果
+    Batch {
+        /// 查询文件，扩展名为 .json 时按 JSON 读取，否则按 CSV 读取
+        file: std::path::PathBuf,
+        /// 结果输出文件，不填则输出到标准输出
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// 计算同行的灰烬植物（或玉米炮）在目标时间命中僵尸的概率
+    Hit {
+        #[command(flatten)]
+        scenario: ScenarioArgs,
+        /// 爆炸类型：cob、cherry、doom、jalapeno
+        #[arg(short, long)]
+        plant: Explosion,
+        /// 落点列数，可为小数
+        #[arg(short, long, required_unless_present = "x", conflicts_with = "x", allow_negative_numbers = true, value_parser = parse_col)]
+        col: Option<f64>,
+        /// 爆心横坐标
+        #[arg(short, allow_negative_numbers = true, value_parser = parse_center_x)]
+        x: Option<i64>,
+        /// 场景：yard、pool、roof，填写后按行输出命中概率
+        #[arg(short, long, requires = "row")]
+        scene: Option<Scene>,
+        /// 落点行数
+        #[arg(short = 'R', long, requires = "scene")]
+        row: Option<i64>,
+    },
+    /// 查找取整坐标不大于 x 的概率超过阈值的最早时间，以及未到达的概率超过阈值的最晚时间
+    Reach {
+        #[command(flatten)]
+        zombie: ZombieArgs,
+        /// 目标坐标
+        #[arg(short)]
+        x: i64,
+        /// 概率阈值
+        #[arg(short, long, default_value_t = 0.01)]
+        prob: f64,
+        /// 搜索的时间范围
+        #[arg(long, num_args = 2, value_names = ["FROM", "TO"], default_values_t = [1, 3000])]
+        window: Vec<i64>,
+        /// 同时输出搜索范围内每隔 STEP 的到达概率
+        #[arg(long, value_name = "STEP")]
+        curve: Option<i64>,
+    },
+    /// 计算取整坐标第一次不大于 x 的时间的分布（如开始啃食某列植物、触发小推车的时间）
+    Passage {
+        #[command(flatten)]
+        zombie: ZombieArgs,
+        /// 目标坐标
+        #[arg(short)]
+        x: i64,
+        /// 搜索的时间范围
+        #[arg(long, num_args = 2, value_names = ["FROM", "TO"], default_values_t = [1, 3000])]
+        window: Vec<i64>,
+    },
+    /// 在时间范围内查找同行灰烬植物（或玉米炮）命中概率最大的时间
+    Timing {
+        #[command(flatten)]
+        zombie: ZombieArgs,
+        /// 爆炸类型：cob、cherry、doom、jalapeno
+        #[arg(short, long)]
+        plant: Explosion,
+        /// 落点列数，可为小数
+        #[arg(short, long, required_unless_present = "x", conflicts_with = "x", allow_negative_numbers = true, value_parser = parse_col)]
+        col: Option<f64>,
+        /// 爆心横坐标
+        #[arg(short, allow_negative_numbers = true, value_parser = parse_center_x)]
+        x: Option<i64>,
+        /// 搜索的时间范围
+        #[arg(long, num_args = 2, value_names = ["FROM", "TO"], required = true)]
+        window: Vec<i64>,
+        /// 同时输出命中概率不低于此值的所有时间
+        #[arg(long)]
+        threshold: Option<f64>,
+    },
+    /// 计算同一只僵尸在多个时间同时被命中（或位于给定坐标范围）的概率
+    Joint {
+        #[command(flatten)]
+        zombie: ZombieArgs,
+        /// 同行爆炸，格式为 TIME:PLANT:COL，如 300:cob:9，可以填多次
+        #[arg(long, value_name = "TIME:PLANT:COL", value_parser = parse_hit, required_unless_present = "at")]
+        hit: Vec<HitArg>,
+        /// 坐标范围，格式为 TIME:L:R，如 300:0:700，可以填多次
+        #[arg(long, value_name = "TIME:L:R", value_parser = parse_at)]
+        at: Vec<PosEvent>,
+    },
+    /// 计算一系列同行爆炸后僵尸死亡的概率，考虑僵尸血量以及各次命中之间的相关性
+    Kill {
+        #[command(flatten)]
+        zombie: ZombieArgs,
+        /// 同行爆炸，格式为 TIME:PLANT:COL[:DAMAGE]，伤害默认为 1800，可以填多次
+        #[arg(long, value_name = "TIME:PLANT:COL[:DAMAGE]", value_parser = parse_hit, required = true)]
+        hit: Vec<HitArg>,
+    },
+    /// 计算多只相互独立的僵尸中最左（最靠前）和最右一只的坐标分布
+    Group {
+        /// 僵尸类型及数量，格式为 TYPE[:COUNT]，如 giga:10，数量默认为 1，可以填多次
+        #[arg(short = 'z', long = "zombie", value_name = "TYPE[:COUNT]", value_parser = parse_group, required = true)]
+        zombies: Vec<(String, u32)>,
+        /// 目标时间
+        #[arg(short, long)]
+        time: i64,
+        #[command(flatten)]
+        events: EventArgs,
+        /// 关注的坐标范围，填写后输出最左、最右一只以及所有僵尸在范围内的概率
+        #[arg(short, long, num_args = 2, value_names = ["L", "R"], allow_negative_numbers = true)]
+        range: Vec<i64>,
+    },
+    /// 按出怪权重估计一波僵尸的组成，以及整波僵尸在目标时间的位置
+    Wave {
+        /// 关卡允许出现的僵尸类型，可以填多次，不填则为数据中的所有类型
+        #[arg(short, long)]
+        zombie: Vec<String>,
+        /// 场景：yard、pool、roof
+        #[arg(short, long, default_value_t = Scene::Yard)]
+        scene: Scene,
+        /// 第 1-5 波
+        #[arg(long)]
+        early: bool,
+        /// 一波的僵尸数量，至少为 1
+        #[arg(short = 'n', long, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// 目标时间
+        #[arg(short, long)]
+        time: i64,
+        #[command(flatten)]
+        events: EventArgs,
+        /// 关注的坐标范围，填写后输出整波僵尸都在范围内的概率
+        #[arg(short, long, num_args = 2, value_names = ["L", "R"], allow_negative_numbers = true)]
+        range: Vec<i64>,
+    },
+    /// 输出所有僵尸在时间范围内的坐标表（精确坐标范围和取整坐标的分位数），text 格式为 Markdown 表格
+    Table {
+        /// 僵尸类型，可以填多次，不填则为数据中的所有类型
+        #[arg(short, long)]
+        zombie: Vec<String>,
+        #[command(flatten)]
+        events: EventArgs,
+        /// 时间范围
+        #[arg(long, num_args = 2, value_names = ["FROM", "TO"], default_values_t = [100, 3000])]
+        window: Vec<i64>,
+        /// 时间间隔
+        #[arg(long, default_value_t = 100)]
+        step: i64,
+        /// 分位数，可以填多次
+        #[arg(short, long, default_values_t = [0.01, 0.5, 0.99])]
+        quantile: Vec<f64>,
+        /// 结果输出文件，不填则输出到标准输出
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+// 命令行中的一次爆炸
+#[derive(Clone, Copy)]
+pub(crate) struct HitArg {
+    pub(crate) time: i64,
+    pub(crate) plant: Explosion,
+    pub(crate) col: f64,
+    damage: Option<i32>,
+}
+
+impl HitArg {
+    pub(crate) fn strike(self) -> Strike {
+        let strike = Strike::new(self.time, self.plant, self.plant.center_x(self.col));
+        return Strike { damage: self.damage.unwrap_or(strike.damage), ..strike };
+    }
+}
+
+fn parse_hit(s: &str) -> Result<HitArg, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let (time, plant, col, damage) = match parts[..] {
+        [time, plant, col] => (time, plant, col, None),
+        [time, plant, col, damage] => (time, plant, col, Some(damage)),
+        _ => return Err("格式应为 TIME:PLANT:COL[:DAMAGE]".to_string()),
+    };
+    return Ok(HitArg {
+        time: time.parse().map_err(|_| format!("无效的时间 {time}"))?,
+        plant: Explosion::from_str(plant).map_err(|_| format!("未知的爆炸类型 {plant}"))?,
+        col: parse_col(col).map_err(|e| e.to_string())?,
+        damage: damage.map(|x| x.parse().map_err(|_| format!("无效的伤害 {x}"))).transpose()?,
+    });
+}
+
+// 列数和爆心横坐标超出场地时计算结果没有意义
+fn parse_col(s: &str) -> Result<f64, Error> {
+    return match s.trim().parse::<f64>() {
+        Ok(col) if COLS.contains(&col) => Ok(col),
+        Ok(_) => Err(Error::InvalidColumn(s.to_string())),
+        Err(_) => Err(Error::InvalidNumber(s.to_string())),
+    };
+}
+
+fn parse_center_x(s: &str) -> Result<i64, Error> {
+    return match s.trim().parse::<i64>() {
+        Ok(x) if CENTER_XS.contains(&x) => Ok(x),
+        Ok(x) => Err(Error::InvalidCenter(x)),
+        Err(_) => Err(Error::InvalidNumber(s.to_string())),
+    };
+}
+
+// 僵尸名字在读取数据后才能检查
+fn parse_group(s: &str) -> Result<(String, u32), String> {
+    let (name, count) = s.split_once(':').unwrap_or((s, "1"));
+    let count = match count.parse() {
+        Ok(x) if x > 0 => x,
+        _ => return Err(format!("无效的数量 {count}，数量至少为 1")),
+    };
+    return Ok((name.to_string(), count));
+}
+
+// 按读取的数据确定各个僵尸类型，不填时为数据中的所有僵尸
+fn zombie_names(db: &ZombieDb, names: &[String]) -> Result<Vec<ZombieName>, Error> {
+    if names.is_empty() {
+        return Ok(db.names().to_vec());
+    }
+    return names.iter().map(|x| db.resolve(x)).collect();
+}
+
+fn parse_at(s: &str) -> Result<PosEvent, String> {
+    let parts: Result<Vec<i64>, _> = s.split(':').map(str::parse).collect();
+    return match parts.as_deref() {
+        Ok(&[time, l, r]) if l <= r => Ok(PosEvent { time: time, range: (l, r) }),
+        _ => Err("格式应为 TIME:L:R，且 L 不大于 R".to_string()),
+    };
+}
+
+fn check_window(window: &[i64]) -> Result<(), Error> {
+    if window[0] > window[1] {
+        return Err(Error::InvalidWindow(window[0], window[1]));
+    }
+    return check_time(window[0]);
+}
+
+fn run_command(db: &ZombieDb, command: &QueryCommand, format: OutputFormat) -> Result<(), Error> {
+    match command {
+        QueryCommand::Batch { file, output } => return run_batch_file(db, file, output.as_deref(), format),
+        QueryCommand::Hit { scenario, plant, col, x, scene, row } => {
+            check_time(scenario.time)?;
+            let center_x = x.unwrap_or_else(|| plant.center_x(col.unwrap()));
+            let scenario = scenario.scenario(db)?;
+            return match (scene, row) {
+                (Some(scene), Some(row)) => {
+                    if !(1..=scene.rows()).contains(row) {
+                        return Err(Error::InvalidRow(*scene, *row));
+                    }
+                    run_row_hit(db, &scenario, *plant, *scene, center_x, *row, format)
+                },
+                _ => run_hit(db, &scenario, *plant, center_x, format),
+            };
+        },
+        QueryCommand::Reach { zombie, x, prob, window, curve } => {
+            check_window(window)?;
+            let scenario = zombie.scenario(db, window[1])?;
+            return run_reach(db, &scenario, *x, *prob, (window[0], window[1]), *curve, format);
+        },
+        QueryCommand::Passage { zombie, x, window } => {
+            check_window(window)?;
+            let scenario = zombie.scenario(db, window[1])?;
+            return run_passage(db, &scenario, *x, (window[0], window[1]), format);
+        },
+        QueryCommand::Timing { zombie, plant, col, x, window, threshold } => {
+            check_window(window)?;
+            let center_x = x.unwrap_or_else(|| plant.center_x(col.unwrap()));
+            let scenario = zombie.scenario(db, window[1])?;
+            return run_timing(db, &scenario, *plant, center_x, (window[0], window[1]), *threshold, format);
+        },
+        QueryCommand::Joint { zombie, hit, at } => return run_joint(db, db.resolve(&zombie.zombie)?, &zombie.events, hit, at, format),
+        QueryCommand::Kill { zombie, hit } => return run_kill(db, db.resolve(&zombie.zombie)?, &zombie.events, hit, format),
+        QueryCommand::Group { zombies, time, events, range } => {
+            check_range(range)?;
+            check_time(*time)?;
+            let scenarios: Vec<Scenario> = zombies.iter()
+                .map(|x| Ok(events.scenario(db.resolve(&x.0)?, *time)))
+                .collect::<Result<_, Error>>()?;
+            let counts: Vec<u32> = zombies.iter().map(|x| x.1).collect();
+            return run_group(db, &scenarios, &counts, range, format);
+        },
+        QueryCommand::Wave { zombie, scene, early, count, time, events, range } => {
+            check_range(range)?;
+            check_time(*time)?;
+            let zombies = zombie_names(db, zombie)?;
+            let composition = wave::composition(db, &zombies, *scene, events.hugewave, *early)?;
+            let (first, _) = composition.first().ok_or(Error::NoSpawn(*scene))?;
+            let scenario = events.scenario(first.clone(), *time);
+            return run_wave(db, &composition, *count, &scenario, range, format);
+        },
+        QueryCommand::Table { zombie, events, window, step, quantile, output } => {
+            check_window(window)?;
+            if *step <= 0 {
+                return Err(Error::InvalidStep(*step));
+            }
+            if let Some(&p) = quantile.iter().find(|p| !(0.0..=1.0).contains(*p)) {
+                return Err(Error::InvalidProbability(p));
+            }
+            let zombies = zombie_names(db, zombie)?;
+            let first = zombies.first().ok_or(Error::EmptyData)?;
+            let scenario = events.scenario(first.clone(), window[1]);
+            let rows = table::coordinate_table(db, &zombies, &scenario, (window[0], window[1]), *step, quantile)?;
+            return write_table(&rows, quantile, output.as_deref(), format);
+        },
+    }
+}
+
+impl Cli {
+    /// 执行命令行指定的查询，不带参数时进入交互模式。出错时返回错误，由调用者输出；
+    /// `validate` 发现数据有错误时返回 `ExitCode::FAILURE`
+    pub fn run(self) -> Result<ExitCode, Error> {
+        // 检查数据时不读取数据库，否则有误的数据会直接报错
+        if let Some(Command::Validate) = self.command {
+            let valid = match &self.data {
+                Some(path) => run_validate(&std::fs::read(path).map_err(|e| Error::Io(path.clone(), e))?, self.format)?,
+                None => run_validate(BUILTIN_DATA, self.format)?,
+            };
+            return Ok(if valid {ExitCode::SUCCESS} else {ExitCode::FAILURE});
+        }
+        let loaded;
+        let db = match &self.data {
+            Some(path) => {
+                loaded = ZombieDb::from_file(path)?;
+                &loaded
+            },
+            None => ZombieDb::builtin(),
+        };
+        if let Some(Command::Query(command)) = &self.command {
+            run_command(db, command, self.format)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        let (Some(zombie), Some(time)) = (&self.zombie, self.time) else {
+            if !self.events.is_empty() {
+                Cli::command().error(clap::error::ErrorKind::MissingRequiredArgument,
+                                     "填写查询参数时需要同时填写 --zombie 和 --time").exit();
+            }
+            repl(db, self.format);
+            return Ok(ExitCode::SUCCESS);
+        };
+        check_range(&self.range)?;
+        check_time(time)?;
+        let scenario = self.events.scenario(db.resolve(zombie)?, time);
+        print_result(db, &scenario, &self.range, self.format)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+}
@@ -0,0 +1,531 @@
+use crate::cli::{EventArgs, HitArg, OutputFormat};
+use zombie_movement_calculator::common::*;
+use zombie_movement_calculator::{batch, group, output, search, table, validate, wave};
+use zombie_movement_calculator::{Error, Explosion, PosEvent, Scene, Strike, ZombieDb};
+use std::io::Write;
+
+// 读取一行，输入结束时返回 None
+fn getline(prompt: &str) -> Option<String> {
+    eprint!("{prompt}");
+    std::io::stderr().flush().ok()?;
+    let mut result = String::new();
+    return match std::io::stdin().read_line(&mut result) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(result),
+    };
+}
+
+fn parse_numbers(s: &str) -> Result<Vec<i64>, Error> {
+    return s.split_whitespace().map(|x| x.parse().map_err(|_| Error::InvalidNumber(x.to_string()))).collect();
+}
+
+// 对坐标范围求和，接近 1 时视为 1
+fn range_prob(dc: bool, d: &PosDistribution, range: &[i64]) -> Option<f64> {
+    return match range {
+        [x] => Some(d.prob(*x)),
+        [l, r] => Some(d.prob_range_rounded(*l, *r, dc)),
+        _ => None
+    };
+}
+
+// 舞王秘籍僵尸的分布是近似计算的，求和和输出时的容差更大
+fn is_dancecheat(db: &ZombieDb, zombie: &ZombieName) -> Result<bool, Error> {
+    return Ok(matches!(db.get(zombie)?.movement_type, MovementType::DanceCheat));
+}
+
+pub(crate) fn print_result(db: &ZombieDb, scenario: &Scenario, range: &[i64], format: OutputFormat) -> Result<(), Error> {
+    let d = db.calculate(scenario)?;
+    let dc = is_dancecheat(db, &scenario.zombie_type)?;
+    let prob = range_prob(dc, &d, range);
+    match format {
+        OutputFormat::Text => print_text(dc, &d, range, prob),
+        OutputFormat::Json => {
+            let report = output::QueryReport {
+                scenario: scenario,
+                range: range.first().map(|&l| (l, *range.last().unwrap())),
+                prob: prob,
+                distribution: &d,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => match prob {
+            Some(prob) => println!("range_l,range_r,prob\n{},{},{prob}", range[0], range[range.len() - 1]),
+            None => output::write_dist_csv(std::io::stdout().lock(), &d).map_err(|e| Error::Write(None, e.into()))?,
+        },
+    }
+    return Ok(());
+}
+
+fn print_text(dc: bool, d: &PosDistribution, range: &[i64], prob: Option<f64>) {
+    if let Some(prob) = prob {
+        if range.len() == 1 {
+            println!("{}: {prob}", range[0]);
+        } else {
+            println!("{}-{}: {prob}", range[0], range[1]);
+        }
+    } else {
+        print_dist(d, if dc {1e-9} else {1e-12});
+    }
+}
+
+// 输出精确坐标范围和概率大于 tol 的部分
+fn print_dist(d: &PosDistribution, tol: f64) {
+    let Some((first, last)) = d.support(tol) else {
+        println!("[]");
+        return;
+    };
+    let pos_min = (d.min * 1000.0).floor() / 1000.0;
+    let pos_max = (d.max * 1000.0).floor() / 1000.0;
+    print!("{pos_min:.03}-{pos_max:.03}: [");
+    for x in &d.slice(first, last)[..(last - first) as usize] {
+        print!("{x:.3e}, ");
+    }
+    println!("{:.3e}]", d.prob(last));
+}
+
+pub(crate) fn repl(db: &ZombieDb, format: OutputFormat) {
+    eprintln!("交互模式只支持冰时机，其他选项（见 --help）需要使用命令行参数，输入 exit 退出");
+    while let Some(zombie_type) = getline("请输入僵尸类型: ") {
+        let zombie_type = zombie_type.trim();
+        if zombie_type == "exit" {
+            break;
+        }
+        let Ok(zombie) = db.resolve(zombie_type) else {
+            eprintln!("请确认僵尸类型是否拼写正确");
+            continue;
+        };
+        match read_query(zombie) {
+            Some(Ok((scenario, range))) => {
+                if let Err(e) = print_result(db, &scenario, &range, format) {
+                    eprintln!("{e}");
+                }
+            },
+            Some(Err(e)) => eprintln!("{e}"),
+            None => break,
+        }
+    }
+}
+
+// 交互模式下读取冰时机、目标时间和坐标范围，输入结束时返回 None
+fn read_query(zombie: ZombieName) -> Option<Result<(Scenario, Vec<i64>), Error>> {
+    let ice_times = getline("请输入冰时间（不填直接换行，多个冰用空格分隔）: ")?;
+    let time = getline("请输入目标时间: ")?;
+    let range = getline("请输入关注的坐标范围（可不填，可填单个坐标，可填用空格分隔的左右边界）: ")?;
+    return Some(parse_query(zombie, &ice_times, time.trim(), &range));
+}
+
+fn parse_query(zombie: ZombieName, ice_times: &str, time: &str, range: &str) -> Result<(Scenario, Vec<i64>), Error> {
+    let ice_times = parse_numbers(ice_times)?;
+    let time = time.parse().map_err(|_| Error::InvalidNumber(time.to_string()))?;
+    let range = parse_numbers(range)?;
+    if let [l, r] = range[..] {
+        if l > r {
+            return Err(Error::InvalidRange(l, r));
+        }
+    }
+    let scenario = Scenario { ice_times: ice_times, ..Scenario::new(zombie, time) };
+    scenario.validate()?;
+    return Ok((scenario, range));
+}
+
+pub(crate) fn check_range(range: &[i64]) -> Result<(), Error> {
+    if range.len() == 2 && range[0] > range[1] {
+        return Err(Error::InvalidRange(range[0], range[1]));
+    }
+    return Ok(());
+}
+
+pub(crate) fn check_time(time: i64) -> Result<(), Error> {
+    return Scenario::new(ZombieType::Regular, time).validate();
+}
+
+// 结果输出到文件，不填时输出到标准输出
+fn create_output(output: Option<&std::path::Path>) -> Result<Box<dyn Write>, Error> {
+    return Ok(match output {
+        Some(path) => Box::new(std::fs::File::create(path).map_err(|e| Error::Write(Some(path.to_path_buf()), e))?),
+        None => Box::new(std::io::stdout().lock()),
+    });
+}
+
+pub(crate) fn run_batch_file(db: &ZombieDb, file: &std::path::Path, output: Option<&std::path::Path>,
+                             format: OutputFormat) -> Result<(), Error> {
+    let reader = std::io::BufReader::new(std::fs::File::open(file).map_err(|e| Error::Io(file.to_path_buf(), e))?);
+    let queries = if file.extension().is_some_and(|x| x.eq_ignore_ascii_case("json")) {
+        batch::read_batch_json(reader).map_err(|e| e.to_string())
+    } else {
+        batch::read_batch_csv(reader).map_err(|e| e.to_string())
+    };
+    let queries = queries.map_err(|e| Error::QueryFile(file.to_path_buf(), e))?;
+    // 出错的查询不输出结果，逐个报告后继续计算其他查询
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for (i, result) in batch::run_batch(db, &queries).into_iter().enumerate() {
+        match result {
+            Ok(x) => results.push(x),
+            Err(e) => {
+                eprintln!("{}", Error::Query(i, Box::new(e)));
+                failed += 1;
+            },
+        }
+    }
+    let writer = create_output(output)?;
+    let written = match format {
+        OutputFormat::Json => serde_json::to_writer(writer, &results).map_err(std::io::Error::from),
+        OutputFormat::Text | OutputFormat::Csv => batch::write_batch_csv(writer, &results).map_err(std::io::Error::from),
+    };
+    written.map_err(|e| Error::Write(output.map(std::path::Path::to_path_buf), e))?;
+    if failed > 0 {
+        return Err(Error::FailedQueries(failed, queries.len()));
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_hit(db: &ZombieDb, scenario: &Scenario, explosion: Explosion, center_x: i64, format: OutputFormat) -> Result<(), Error> {
+    let data = db.get(&scenario.zombie_type)?;
+    let d = db.calculate(scenario)?;
+    let prob = explosion.hit_prob(data, &d, center_x);
+    match format {
+        OutputFormat::Text => println!("{explosion} {center_x}: {prob}"),
+        OutputFormat::Json => {
+            let report = output::HitReport {
+                scenario: scenario,
+                explosion: explosion,
+                center_x: center_x,
+                hit_range: explosion.hit_range(data, center_x),
+                prob: prob,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => println!("explosion,center_x,prob\n{explosion},{center_x},{prob}"),
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_row_hit(db: &ZombieDb, scenario: &Scenario, explosion: Explosion, scene: Scene,
+                          center_x: i64, center_row: i64, format: OutputFormat) -> Result<(), Error> {
+    let data = db.get(&scenario.zombie_type)?;
+    let d = db.calculate(scenario)?;
+    let rows = explosion.row_hit_probs(data, &d, scene, center_x, center_row);
+    match format {
+        OutputFormat::Text => {
+            for (i, prob) in rows.iter().enumerate() {
+                println!("{}: {prob}", i + 1);
+            }
+        },
+        OutputFormat::Json => {
+            let report = output::RowHitReport {
+                scenario: scenario,
+                explosion: explosion,
+                scene: scene,
+                center_x: center_x,
+                center_row: center_row,
+                rows: rows,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => {
+            println!("row,prob");
+            for (i, prob) in rows.iter().enumerate() {
+                println!("{},{prob}", i + 1);
+            }
+        },
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_reach(db: &ZombieDb, scenario: &Scenario, x: i64, threshold: f64, window: (i64, i64),
+                        curve: Option<i64>, format: OutputFormat) -> Result<(), Error> {
+    let times = search::reach_times(db, scenario, x, threshold, window)?;
+    let curve = curve.map(|step| search::reach_curve(db, scenario, x, window, step)).transpose()?;
+    let show = |t: Option<i64>| t.map_or("无".to_string(), |t| t.to_string());
+    match format {
+        OutputFormat::Text => {
+            println!("最早: {}", show(times.first));
+            println!("最晚: {}", show(times.last));
+            for (t, prob) in curve.iter().flatten() {
+                println!("{t}: {prob}");
+            }
+        },
+        OutputFormat::Json => {
+            let report = output::ReachReport {
+                scenario: scenario,
+                x: x,
+                threshold: threshold,
+                window: window,
+                times: times,
+                curve: curve,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => match curve {
+            Some(curve) => {
+                println!("time,prob");
+                for (t, prob) in curve {
+                    println!("{t},{prob}");
+                }
+            },
+            None => println!("first,last\n{},{}",
+                             times.first.map_or(String::new(), |t| t.to_string()),
+                             times.last.map_or(String::new(), |t| t.to_string())),
+        },
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_passage(db: &ZombieDb, scenario: &Scenario, x: i64, window: (i64, i64), format: OutputFormat) -> Result<(), Error> {
+    let d = search::first_passage(db, scenario, x, window)?;
+    let nonzero = || d.dist.iter().enumerate()
+        .filter(|(_, &p)| p > 0.0)
+        .map(|(i, &p)| (d.offset + i as i64, p));
+    match format {
+        OutputFormat::Text => {
+            for (t, p) in nonzero() {
+                println!("{t}: {p}");
+            }
+            println!("未到达: {}", d.remaining);
+        },
+        OutputFormat::Json => {
+            let report = output::PassageReport { scenario: scenario, x: x, window: window, distribution: &d };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => {
+            println!("time,prob");
+            for (t, p) in nonzero() {
+                println!("{t},{p}");
+            }
+        },
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_timing(db: &ZombieDb, scenario: &Scenario, explosion: Explosion, center_x: i64, window: (i64, i64),
+                         threshold: Option<f64>, format: OutputFormat) -> Result<(), Error> {
+    let curve = search::hit_curve(db, scenario, explosion, center_x, window)?;
+    let best = search::best_time(&curve);
+    let ranges = threshold.map(|p| search::time_ranges(&curve, p));
+    match format {
+        OutputFormat::Text => {
+            if let Some((t, p)) = best {
+                println!("最佳: {t} ({p})");
+            }
+            if let Some(ranges) = &ranges {
+                let strs: Vec<String> = ranges.iter()
+                    .map(|&(l, r)| if l == r {l.to_string()} else {format!("{l}-{r}")})
+                    .collect();
+                println!("不低于 {}: {}", threshold.unwrap(), if strs.is_empty() {"无".to_string()} else {strs.join(", ")});
+            }
+        },
+        OutputFormat::Json => {
+            let report = output::TimingReport {
+                scenario: scenario,
+                explosion: explosion,
+                center_x: center_x,
+                window: window,
+                best: best,
+                threshold: threshold,
+                ranges: ranges,
+                curve: curve,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => {
+            println!("time,prob");
+            for (t, p) in curve {
+                println!("{t},{p}");
+            }
+        },
+    }
+    return Ok(());
+}
+
+// 命令行要求至少填写一个 --hit 或 --at，目标时间取最晚的时间
+pub(crate) fn run_joint(db: &ZombieDb, zombie: ZombieName, args: &EventArgs, hit: &[HitArg], at: &[PosEvent],
+                        format: OutputFormat) -> Result<(), Error> {
+    let data = db.get(&zombie)?;
+    let events: Vec<PosEvent> = hit.iter()
+        .map(|x| x.plant.hit_event(data, x.time, x.plant.center_x(x.col)))
+        .chain(at.iter().copied())
+        .collect();
+    let scenario = args.scenario(zombie, events.iter().map(|x| x.time).max().unwrap_or(0));
+    let joint = db.joint_prob(&scenario, &events)?;
+    let marginals: Vec<f64> = events.iter().map(|event| {
+        let d = db.calculate(&Scenario { time: event.time, ..scenario.clone() })?;
+        Ok(d.prob_range(event.range.0, event.range.1))
+    }).collect::<Result<_, Error>>()?;
+    match format {
+        OutputFormat::Text => {
+            for (event, prob) in events.iter().zip(&marginals) {
+                println!("{} {}-{}: {prob}", event.time, event.range.0, event.range.1);
+            }
+            println!("同时满足: {joint}");
+        },
+        OutputFormat::Json => {
+            let report = output::JointReport { scenario: &scenario, events: &events, marginals: marginals, joint: joint };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => {
+            println!("time,range_l,range_r,prob");
+            for (event, prob) in events.iter().zip(&marginals) {
+                println!("{},{},{},{prob}", event.time, event.range.0, event.range.1);
+            }
+            println!("all,,,{joint}");
+        },
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_kill(db: &ZombieDb, zombie: ZombieName, args: &EventArgs, hit: &[HitArg],
+                       format: OutputFormat) -> Result<(), Error> {
+    let data = db.get(&zombie)?;
+    let strikes: Vec<Strike> = hit.iter().map(|x| x.strike()).collect();
+    let scenario = args.scenario(zombie, strikes.iter().map(|x| x.time).max().unwrap_or(0));
+    let damage = db.damage_distribution(&scenario, &strikes)?;
+    let kill = db.kill_prob(&scenario, &strikes)?;
+    match format {
+        OutputFormat::Text => {
+            for (total, prob) in &damage {
+                println!("{total}: {prob}");
+            }
+            println!("死亡（血量 {}）: {kill}", data.hp);
+        },
+        OutputFormat::Json => {
+            let report = output::KillReport {
+                scenario: &scenario,
+                hp: data.hp,
+                strikes: &strikes,
+                damage: damage,
+                kill: kill,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => {
+            println!("damage,prob");
+            for (total, prob) in &damage {
+                println!("{total},{prob}");
+            }
+        },
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_group(db: &ZombieDb, scenarios: &[Scenario], counts: &[u32], range: &[i64], format: OutputFormat) -> Result<(), Error> {
+    let dists: Vec<PosDistribution> = scenarios.iter().map(|x| db.calculate(x)).collect::<Result<_, Error>>()?;
+    let group: Vec<(&PosDistribution, u32)> = dists.iter().zip(counts.iter().copied()).collect();
+    let front = group::frontmost(&group);
+    let back = group::backmost(&group);
+    let range = range.first().map(|&l| (l, *range.last().unwrap()));
+    let all = range.map(|(l, r)| group::all_in_range(&group, l, r));
+    let dc = scenarios.iter().map(|x| is_dancecheat(db, &x.zombie_type)).collect::<Result<Vec<bool>, Error>>()?.contains(&true);
+    let tol = if dc {1e-9} else {1e-12};
+    match format {
+        OutputFormat::Text => {
+            if let (Some((l, r)), Some(all)) = (range, all) {
+                println!("最左 {l}-{r}: {}", front.prob_range(l, r));
+                println!("最右 {l}-{r}: {}", back.prob_range(l, r));
+                println!("全部 {l}-{r}: {all}");
+            } else {
+                print!("最左 ");
+                print_dist(&front, tol);
+                print!("最右 ");
+                print_dist(&back, tol);
+            }
+        },
+        OutputFormat::Json => {
+            let report = output::GroupReport {
+                scenarios: scenarios,
+                counts: counts,
+                range: range,
+                all_in_range: all,
+                frontmost: &front,
+                backmost: &back,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => {
+            println!("x,frontmost,backmost");
+            if let (Some(front_support), Some(back_support)) = (front.support(0.0), back.support(0.0)) {
+                for x in min(front_support.0, back_support.0)..=max(front_support.1, back_support.1) {
+                    println!("{x},{},{}", front.prob(x), back.prob(x));
+                }
+            }
+        },
+    }
+    return Ok(());
+}
+
+pub(crate) fn run_wave(db: &ZombieDb, composition: &[(ZombieName, f64)], count: u32, scenario: &Scenario,
+                       range: &[i64], format: OutputFormat) -> Result<(), Error> {
+    let d = wave::mixture(db, composition, scenario)?;
+    let front = group::frontmost(&[(&d, count)]);
+    let range = range.first().map(|&l| (l, *range.last().unwrap()));
+    let all = range.map(|(l, r)| group::all_in_range(&[(&d, count)], l, r));
+    let dc = composition.iter().map(|x| is_dancecheat(db, &x.0)).collect::<Result<Vec<bool>, Error>>()?.contains(&true);
+    let tol = if dc {1e-9} else {1e-12};
+    match format {
+        OutputFormat::Text => {
+            for (zombie_type, p) in composition {
+                println!("{zombie_type}: {p} ({} 只)", p * f64::from(count));
+            }
+            if let (Some((l, r)), Some(all)) = (range, all) {
+                println!("最左 {l}-{r}: {}", front.prob_range(l, r));
+                println!("全部 {l}-{r}: {all}");
+            } else {
+                print!("最左 ");
+                print_dist(&front, tol);
+            }
+        },
+        OutputFormat::Json => {
+            let report = output::WaveReport {
+                scenario: scenario,
+                count: count,
+                composition: composition.iter()
+                    .map(|(zombie_type, p)| output::WaveEntry { zombie_type: zombie_type.clone(), prob: *p, expected: p * f64::from(count) })
+                    .collect(),
+                range: range,
+                all_in_range: all,
+                frontmost: &front,
+            };
+            println!("{}", serde_json::to_string(&report).unwrap());
+        },
+        OutputFormat::Csv => {
+            println!("zombie,prob,expected");
+            for (zombie_type, p) in composition {
+                println!("{zombie_type},{p},{}", p * f64::from(count));
+            }
+        },
+    }
+    return Ok(());
+}
+
+pub(crate) fn write_table(rows: &[table::TableRow], quantiles: &[f64], output: Option<&std::path::Path>,
+                          format: OutputFormat) -> Result<(), Error> {
+    let writer = create_output(output)?;
+    let written = match format {
+        OutputFormat::Text => table::write_table_markdown(writer, quantiles, rows),
+        OutputFormat::Json => serde_json::to_writer(writer, rows).map_err(std::io::Error::from),
+        OutputFormat::Csv => table::write_table_csv(writer, quantiles, rows).map_err(std::io::Error::from),
+    };
+    return written.map_err(|e| Error::Write(output.map(std::path::Path::to_path_buf), e));
+}
+
+// 数据有错误时返回 false
+pub(crate) fn run_validate(file_content: &[u8], format: OutputFormat) -> Result<bool, Error> {
+    let issues = validate::validate(file_content);
+    match format {
+        OutputFormat::Text => {
+            for issue in &issues {
+                println!("{} {}: {}", issue.severity, issue.zombie, issue.message);
+            }
+            if issues.is_empty() {
+                println!("数据没有问题");
+            }
+        },
+        OutputFormat::Json => println!("{}", serde_json::to_string(&issues).unwrap()),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout().lock());
+            issues.iter().try_for_each(|x| writer.serialize(x)).map_err(|e| Error::Write(None, e.into()))?;
+            writer.flush().map_err(|e| Error::Write(None, e))?;
+        },
+    }
+    return Ok(!issues.iter().any(|x| x.severity == validate::Severity::Error));
+}
@@ -2,12 +2,20 @@ pub use std::cmp::{min, max};
 pub use std::collections::{HashMap, HashSet};
 pub use std::str::FromStr;
 pub use strum::IntoEnumIterator;
+use std::num::NonZeroU32;
+use crate::error::Error;
+use num_bigint::BigInt;
+use num_traits::{CheckedMul, ToPrimitive};
 
-#[derive(strum::EnumString, strum::EnumIter, PartialEq, Eq, Hash, Copy, Clone, Debug)]
+/// 内置僵尸类型，可以用别名（如 `Giga`）且不区分大小写。数据按名字索引（见 [`ZombieName`]），
+/// 内置僵尸类型是其名字的别名，数据文件中还可以加入自定义僵尸
+#[derive(strum::EnumString, strum::EnumIter, strum::IntoStaticStr, PartialEq, Eq, Hash, Copy, Clone, Debug)]
 #[strum(serialize_all = "PascalCase", ascii_case_insensitive)]
 pub enum ZombieType {
     Regular,
+    #[strum(serialize = "DCFast")]
     DCFast,
+    #[strum(serialize = "DCSlow")]
     DCSlow,
     Flag,
     Conehead,
@@ -20,7 +28,7 @@ pub enum ZombieType {
     Snorkel,
     Zomboni,
     DolphinRider,
-    #[strum(serialize = "JackInTheBox", serialize = "Jack-in-the-box")]
+    #[strum(to_string = "JackInTheBox", serialize = "Jack-in-the-box")]
     JackInTheBox,
     Balloon,
     Digger,
@@ -32,8 +40,107 @@ pub enum ZombieType {
     GigaGargantuar,
 }
 
+impl std::fmt::Display for ZombieType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.write_str(self.into());
+    }
+}
+
+impl serde::Serialize for ZombieType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ZombieType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        return ZombieType::from_str(&name)
+            .map_err(|_| serde::de::Error::custom(format!("无效的僵尸类型 {name}")));
+    }
+}
+
+/// 僵尸的名字，[`crate::ZombieDb`] 以此为键。内置僵尸类型的名字为 [`ZombieType`] 的标准写法，
+/// 自定义僵尸为数据文件中的写法；在数据中查找时不区分大小写，内置僵尸类型可以用别名
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct ZombieName(String);
+
+impl ZombieName {
+    /// 名字是内置僵尸类型（或其别名）时换成标准写法，否则原样保留，不检查数据中是否有该僵尸
+    pub fn new(name: &str) -> ZombieName {
+        let name = name.trim();
+        return match ZombieType::from_str(name) {
+            Ok(x) => ZombieName::from(x),
+            Err(_) => ZombieName(name.to_string()),
+        };
+    }
+
+    pub fn as_str(&self) -> &str {
+        return &self.0;
+    }
+
+    /// 名字对应的内置僵尸类型，自定义僵尸为 `None`
+    pub fn builtin(&self) -> Option<ZombieType> {
+        return ZombieType::from_str(&self.0).ok();
+    }
+
+    // 在数据中查找时的键，不区分大小写
+    pub(crate) fn key(&self) -> String {
+        return self.0.to_ascii_lowercase();
+    }
+}
+
+impl From<ZombieType> for ZombieName {
+    fn from(zombie_type: ZombieType) -> ZombieName {
+        return ZombieName(zombie_type.to_string());
+    }
+}
+
+impl From<&str> for ZombieName {
+    fn from(name: &str) -> ZombieName {
+        return ZombieName::new(name);
+    }
+}
+
+impl std::fmt::Display for ZombieName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.write_str(&self.0);
+    }
+}
+
+impl serde::Serialize for ZombieName {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(&self.0);
+    }
+}
+
+// 任意名字都可以读取，在数据中查找时才检查
+impl<'de> serde::Deserialize<'de> for ZombieName {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return Ok(ZombieName::new(&String::deserialize(deserializer)?));
+    }
+}
+
 pub type Num = num_rational::Rational64;
 
+/// 各个数之积，超出 [`Num`] 的范围时返回 `None`
+pub fn checked_product(factors: &[Num]) -> Option<Num> {
+    return factors.iter().try_fold(Num::from_integer(1), |acc, x| acc.checked_mul(x));
+}
+
+/// 各个数之积转换为 f64。乘积超出 [`Num`] 的范围（如长时间多次用冰时权重的分母）时改用 `BigRational` 计算，结果仍然精确
+///
+/// # Panics
+///
+/// 有理数转换为 f64 总会成功（超出范围时为无穷大），不会 panic。
+pub fn product_f64(factors: &[Num]) -> f64 {
+    if let Some(x) = checked_product(factors) {
+        return x.to_f64().unwrap();
+    }
+    let big = |x: &Num| num_rational::BigRational::new(BigInt::from(*x.numer()), BigInt::from(*x.denom()));
+    return factors.iter().map(big).product::<num_rational::BigRational>().to_f64().unwrap();
+}
+
 pub enum MovementType {
     Constant,
     Animation(Vec<Num>),
@@ -43,6 +150,19 @@ pub enum MovementType {
     Zomboni,
 }
 
+impl MovementType {
+    /// 是否不会向右移动，此时到达某坐标的概率关于时间单调不减。舞王秘籍僵尸随机游走，动画轨道有负位移时会后退
+    pub fn is_monotone(&self) -> bool {
+        let monotone = |track: &[Num]| track.iter().all(|&x| x >= Num::new(0, 1));
+        return match self {
+            MovementType::Animation(x) | MovementType::Dancing(x) => monotone(x),
+            MovementType::Regular(a, b) => monotone(a) && monotone(b),
+            MovementType::DanceCheat => false,
+            MovementType::Constant | MovementType::Zomboni => true,
+        };
+    }
+}
+
 pub struct ZombieData {
     pub spawn: (i64, i64),
     pub spawn_hugewave: (i64, i64),
@@ -60,8 +180,318 @@ pub struct ZombieData {
     pub if_generate_in_wave1to5: (bool, bool),
 }
 
+/// 一次查询的输入
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Scenario {
+    /// 僵尸的名字，见 [`ZombieName`]
+    pub zombie_type: ZombieName,
+    /// 所有冰的冰时机，不必按时间排序，生效时间不大于 0 的冰会被忽略
+    #[serde(default)]
+    pub ice_times: Vec<i64>,
+    pub time: i64,
+    /// 是否为旗帜波（大波），旗帜波僵尸使用 `spawn_hugewave` 出生范围
+    #[serde(default)]
+    pub hugewave: bool,
+    /// 寒冰射手、冰瓜等只减速不冻结的命中
+    #[serde(default)]
+    pub chills: Vec<Chill>,
+    /// `ice_times` 的含义，默认为生效时间
+    #[serde(default)]
+    pub ice_source: IceSource,
+    /// 玉米投手的黄油命中时间，不大于 0 的会被忽略
+    #[serde(default)]
+    pub butter_times: Vec<i64>,
+}
+
+impl Scenario {
+    pub fn new(zombie_type: impl Into<ZombieName>, time: i64) -> Scenario {
+        return Scenario {
+            zombie_type: zombie_type.into(),
+            ice_times: Vec::new(),
+            time: time,
+            hugewave: false,
+            chills: Vec::new(),
+            ice_source: IceSource::Effective,
+            butter_times: Vec::new(),
+        };
+    }
+
+    /// 检查目标时间是否有效
+    ///
+    /// # Errors
+    ///
+    /// 目标时间为负时返回 [`Error::InvalidTime`]。
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.time < 0 {
+            return Err(Error::InvalidTime(self.time));
+        }
+        return Ok(());
+    }
+
+    /// 换算为生效时间的冰时机，超出 `i64` 范围时取 `i64::MAX`（不会在目标时间前生效）
+    pub fn effective_ice_times(&self) -> Vec<i64> {
+        return self.ice_times.iter().map(|&t| t.saturating_add(self.ice_source.delay())).collect();
+    }
+}
+
+/// 冰时机的来源，决定从种下到生效的延迟
+#[derive(strum::EnumString, strum::EnumIter, strum::Display, PartialEq, Eq, Hash, Copy, Clone, Debug, Default)]
+#[strum(ascii_case_insensitive)]
+pub enum IceSource {
+    /// 冰时机即为生效时间（README 中的“冰时机”）
+    #[default]
+    Effective,
+    /// 白天种下的寒冰菇，种下 100cs 后生效
+    #[strum(to_string = "Regular", serialize = "Normal")]
+    Regular,
+    /// 模仿者寒冰菇，变身 320cs 后再经过 100cs 生效
+    #[strum(to_string = "Imitater", serialize = "Imitator")]
+    Imitater,
+    /// 预先种下的睡眠寒冰菇，冰时机为咖啡豆种下的时间，唤醒 198cs 后再经过 100cs 生效
+    #[strum(to_string = "Ice3", serialize = "Coffee")]
+    Ice3,
+}
+
+impl IceSource {
+    /// 从种下到生效的延迟
+    pub fn delay(self) -> i64 {
+        return match self {
+            IceSource::Effective => 0,
+            IceSource::Regular => 100,
+            IceSource::Imitater => 420,
+            IceSource::Ice3 => 298,
+        };
+    }
+}
+
+impl serde::Serialize for IceSource {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for IceSource {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        return IceSource::from_str(&name)
+            .map_err(|_| serde::de::Error::custom(format!("未知的冰来源 {name}")));
+    }
+}
+
+/// 一组减速命中：第一次在 `start`，之后每隔 `interval` 命中一次，直到 `end`（含）或目标时间，
+/// 每次命中使减速持续到命中后 1000cs。文本格式为 `START[:INTERVAL[:END]]`，`END` 不能早于 `START`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Chill {
+    pub start: i64,
+    pub interval: Option<NonZeroU32>,
+    pub end: Option<i64>,
+}
+
+impl Chill {
+    /// 单次命中
+    pub fn once(start: i64) -> Chill {
+        return Chill { start: start, interval: None, end: None };
+    }
+
+    /// 目标时间前最后一次命中的时间，目标时间前没有命中或 `end` 早于 `start` 时为 `None`
+    pub fn last_hit(self, time: i64) -> Option<i64> {
+        let end = min(self.end.unwrap_or(time), time);
+        if self.start <= 0 || self.start > end {
+            return None;
+        }
+        return Some(match self.interval {
+            Some(interval) => self.start + (end - self.start) / i64::from(interval.get()) * i64::from(interval.get()),
+            None => self.start,
+        });
+    }
+
+    /// 目标时间前的减速区间（左闭右开），每次命中减速到命中后 999cs，重叠或相接的区间会合并，
+    /// 右端超出 `i64` 范围时取 `i64::MAX`
+    pub fn windows(self, time: i64) -> Vec<(i64, i64)> {
+        let Some(last) = self.last_hit(time) else {
+            return Vec::new();
+        };
+        // 只有一次命中时 last == start
+        let step = self.interval.map_or(1, |x| x.get() as usize);
+        let mut result: Vec<(i64, i64)> = Vec::new();
+        for hit in (self.start..=last).step_by(step) {
+            match result.last_mut() {
+                Some(window) if hit <= window.1 => window.1 = hit.saturating_add(999),
+                _ => result.push((hit, hit.saturating_add(999))),
+            }
+        }
+        return result;
+    }
+}
+
+impl std::fmt::Display for Chill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.start)?;
+        if let Some(interval) = self.interval {
+            write!(f, ":{interval}")?;
+            if let Some(end) = self.end {
+                write!(f, ":{end}")?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl FromStr for Chill {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<i64> = s.trim().split(':').map(|x| x.trim().parse::<i64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| Error::InvalidNumber(s.to_string()))?;
+        // 间隔不为正时无法按间隔依次命中
+        let interval = |x: i64| -> Result<Option<NonZeroU32>, Error> {
+            if x <= 0 {
+                return Err(Error::InvalidTime(x));
+            }
+            return u32::try_from(x).map(NonZeroU32::new).map_err(|_| Error::InvalidNumber(s.to_string()));
+        };
+        return match parts.as_slice() {
+            [start] => Ok(Chill::once(*start)),
+            [start, x] => Ok(Chill { start: *start, interval: interval(*x)?, end: None }),
+            // 结束时间早于第一次命中时没有意义，不按只命中一次处理
+            [start, _, end] if end < start => Err(Error::InvalidWindow(*start, *end)),
+            [start, x, end] => Ok(Chill { start: *start, interval: interval(*x)?, end: Some(*end) }),
+            _ => Err(Error::InvalidNumber(s.to_string())),
+        };
+    }
+}
+
+impl serde::Serialize for Chill {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Chill {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        return Chill::from_str(&s).map_err(serde::de::Error::custom);
+    }
+}
+
+/// 取整后横坐标的概率分布，`dist[i]` 为坐标取整后等于 `offset + i` 的概率
+///
+/// 坐标不限于 0-879，僵尸进屋后为负数。
+#[derive(Clone, Debug)]
 pub struct PosDistribution {
-    pub dist: [f64; 880],
+    pub offset: i64,
+    pub dist: Vec<f64>,
+    /// 精确坐标的最小值
     pub min: f64,
+    /// 精确坐标的最大值
     pub max: f64,
 }
+
+impl PosDistribution {
+    // 没有任何概率的分布，min 和 max 分别为正负无穷
+    pub(crate) fn empty() -> PosDistribution {
+        return PosDistribution { offset: 0, dist: Vec::new(), min: f64::INFINITY, max: f64::NEG_INFINITY };
+    }
+
+    // 坐标 x 的概率加上 p，必要时向两端扩展
+    pub(crate) fn add(&mut self, x: i64, p: f64) {
+        if self.dist.is_empty() {
+            self.offset = x;
+        }
+        if x < self.offset {
+            let grow = max(self.offset - x, self.dist.len() as i64);
+            let mut dist = vec![0.0; grow as usize];
+            dist.append(&mut self.dist);
+            self.dist = dist;
+            self.offset -= grow;
+        }
+        let i = (x - self.offset) as usize;
+        if i >= self.dist.len() {
+            self.dist.resize(max(i + 1, self.dist.len() * 2), 0.0);
+        }
+        self.dist[i] += p;
+    }
+
+    // 去掉两端概率为 0 的项
+    pub(crate) fn trim(&mut self) {
+        let Some(first) = self.dist.iter().position(|&x| x != 0.0) else {
+            self.dist.clear();
+            return;
+        };
+        let last = self.dist.iter().rposition(|&x| x != 0.0).unwrap();
+        self.dist.truncate(last + 1);
+        self.dist.drain(..first);
+        self.offset += first as i64;
+    }
+
+    /// 取整后坐标等于 x 的概率
+    pub fn prob(&self, x: i64) -> f64 {
+        return usize::try_from(x - self.offset).ok().and_then(|i| self.dist.get(i)).copied().unwrap_or(0.0);
+    }
+
+    /// 取整后坐标在 [l, r] 内的概率
+    pub fn prob_range(&self, l: i64, r: i64) -> f64 {
+        let last = self.offset + self.dist.len() as i64 - 1;
+        let (l, r) = (max(l, self.offset), min(r, last));
+        if l > r {
+            return 0.0;
+        }
+        let sum: f64 = self.dist[(l - self.offset) as usize..=(r - self.offset) as usize].iter().sum();
+        return sum.min(1.0);
+    }
+
+    /// 取整后坐标在 [l, r] 内的概率，与 1 相差不超过累加误差时视为 1
+    ///
+    /// 舞王秘籍僵尸的分布是近似计算的（`dancecheat` 为真），只有更接近 1 时才视为 1。
+    pub fn prob_range_rounded(&self, l: i64, r: i64, dancecheat: bool) -> f64 {
+        let sum = self.prob_range(l, r);
+        return if sum > 1.0 - (if dancecheat {1e-15} else {1e-12}) {1.0} else {sum};
+    }
+
+    /// 取整坐标不大于 x 的概率至少为 p 的最小 x
+    pub fn quantile(&self, p: f64) -> i64 {
+        let mut sum = 0.0;
+        for (i, prob) in self.dist.iter().enumerate() {
+            sum += prob;
+            if sum >= p - 1e-12 {
+                return self.offset + i as i64;
+            }
+        }
+        return self.offset + self.dist.len() as i64 - 1;
+    }
+
+    /// 概率大于 tol 的第一个和最后一个坐标，没有这样的坐标（如空分布）时为 `None`
+    pub fn support(&self, tol: f64) -> Option<(i64, i64)> {
+        let first = self.dist.iter().position(|&x| x > tol)?;
+        let last = self.dist.iter().rposition(|&x| x > tol)?;
+        return Some((self.offset + first as i64, self.offset + last as i64));
+    }
+
+    /// 坐标 [first, last] 与 `dist` 范围的交集内的概率，没有交集时为空
+    pub fn slice(&self, first: i64, last: i64) -> &[f64] {
+        let end = self.offset + self.dist.len() as i64 - 1;
+        let (first, last) = (max(first, self.offset), min(last, end));
+        if first > last {
+            return &[];
+        }
+        return &self.dist[(first - self.offset) as usize..=(last - self.offset) as usize];
+    }
+}
+
+// 只输出非零的部分，offset 为 dist 第一项对应的坐标
+impl serde::Serialize for PosDistribution {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let (offset, dist) = match self.support(0.0) {
+            Some((first, last)) => (first, self.slice(first, last)),
+            None => (self.offset, &[][..]),
+        };
+        let mut state = serializer.serialize_struct("PosDistribution", 4)?;
+        state.serialize_field("min", &self.min)?;
+        state.serialize_field("max", &self.max)?;
+        state.serialize_field("offset", &offset)?;
+        state.serialize_field("dist", dist)?;
+        return state.end();
+    }
+}
@@ -0,0 +1,109 @@
+/// 解析数据、检查输入时的错误
+#[derive(Debug)]
+pub enum Error {
+    /// 未知的僵尸类型，或数据中没有该僵尸
+    UnknownZombie(String),
+    /// 数据文件无法读取
+    Io(std::path::PathBuf, std::io::Error),
+    /// 数据文件无法按 CSV 读取
+    Csv(csv::Error),
+    /// 数据中同一僵尸出现多次
+    DuplicateZombie(String),
+    /// 僵尸的出生范围、速度等数据有误
+    BadData { zombie: String, reason: String },
+    /// 移动方式未知或参数有误
+    BadMovement { zombie: String, reason: String },
+    /// 无法解析的数值
+    InvalidNumber(String),
+    /// 目标时间为负数，或减速间隔不为正
+    InvalidTime(i64),
+    /// 坐标范围的左边界大于右边界
+    InvalidRange(i64, i64),
+    /// 时间范围的左端大于右端，包括减速命中的结束时间早于第一次命中
+    InvalidWindow(i64, i64),
+    /// 时间间隔不为正
+    InvalidStep(i64),
+    /// 概率（如分位数）不在 0-1 之间
+    InvalidProbability(f64),
+    /// 落点列数不在 [`crate::explosion::COLS`] 内，或不是有限数
+    InvalidColumn(String),
+    /// 爆心横坐标不在 [`crate::explosion::CENTER_XS`] 内
+    InvalidCenter(i64),
+    /// 落点行数超出场景的行数
+    InvalidRow(crate::Scene, i64),
+    /// 场景中没有可以出现的僵尸
+    NoSpawn(crate::Scene),
+    /// 数据中没有任何僵尸（数据文件只有表头）
+    EmptyData,
+    /// 只给出了坐标范围的右边界
+    MissingRangeLeft(i64),
+    /// 该僵尸不支持联合查询（舞王秘籍僵尸）
+    JointUnsupported(String),
+    /// 该僵尸可能向右移动，无法计算首次到达时间（见 [`crate::MovementType::is_monotone`]）
+    NotMonotone(String),
+    /// 联合查询的条件数超过 [`crate::joint::MAX_EVENTS`]
+    TooManyEvents(usize),
+    /// 精确计算的中间结果超出 [`crate::Num`] 的范围
+    Overflow,
+    /// 批量查询文件无法读取
+    QueryFile(std::path::PathBuf, String),
+    /// 批量查询中第 `.0` 个（从 0 开始）查询有误
+    Query(usize, Box<Error>),
+    /// 批量查询中有 `.0` 个查询有误，共 `.1` 个查询
+    FailedQueries(usize, usize),
+    /// 结果无法写入文件（路径为 `Some`）或标准输出
+    Write(Option<std::path::PathBuf>, std::io::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            Error::UnknownZombie(name) => write!(f, "未知的僵尸类型 {name}"),
+            Error::Io(path, e) => write!(f, "无法读取 {}: {e}", path.display()),
+            Error::Csv(e) => write!(f, "数据格式错误: {e}"),
+            Error::DuplicateZombie(name) => write!(f, "数据中 {name} 出现了多次"),
+            Error::BadData { zombie, reason } => write!(f, "{zombie} 的数据有误: {reason}"),
+            Error::BadMovement { zombie, reason } => write!(f, "{zombie} 的移动方式有误: {reason}"),
+            Error::InvalidNumber(s) => write!(f, "无效的数值 {s}"),
+            Error::InvalidTime(time) => write!(f, "无效的时间 {time}，目标时间不能为负数，减速间隔必须为正"),
+            Error::InvalidRange(l, r) => write!(f, "无效的坐标范围 {l}-{r}，左边界不能大于右边界"),
+            Error::InvalidWindow(l, r) => write!(f, "无效的时间范围 {l}-{r}，左端不能大于右端"),
+            Error::InvalidStep(step) => write!(f, "无效的时间间隔 {step}，时间间隔必须为正"),
+            Error::InvalidProbability(p) => write!(f, "无效的概率 {p}，概率应在 0-1 之间"),
+            Error::InvalidColumn(col) => write!(f, "无效的列数 {col}，列数应在 {}-{} 之间",
+                                                crate::explosion::COLS.start(), crate::explosion::COLS.end()),
+            Error::InvalidCenter(x) => write!(f, "无效的爆心横坐标 {x}，应在 {} 到 {} 之间",
+                                              crate::explosion::CENTER_XS.start(), crate::explosion::CENTER_XS.end()),
+            Error::InvalidRow(scene, row) => write!(f, "{scene} 场景只有 {} 行，没有第 {row} 行", scene.rows()),
+            Error::NoSpawn(scene) => write!(f, "{scene} 场景中没有可以出现的僵尸"),
+            Error::EmptyData => write!(f, "数据中没有任何僵尸"),
+            Error::MissingRangeLeft(r) => write!(f, "坐标范围缺少左边界，右边界为 {r}"),
+            Error::JointUnsupported(name) => write!(f, "{name} 不支持联合查询"),
+            Error::NotMonotone(name) => write!(f, "{name} 可能向右移动，不支持计算首次到达时间"),
+            Error::TooManyEvents(n) => write!(f, "联合查询有 {n} 个条件，最多 {} 个", crate::joint::MAX_EVENTS),
+            Error::Overflow => write!(f, "精确计算超出 64 位整数的范围（目标时间过长、用冰次数过多或数据中的小数位数过多）"),
+            Error::QueryFile(path, e) => write!(f, "查询文件 {} 格式错误: {e}", path.display()),
+            Error::Query(i, e) => write!(f, "第 {} 个查询有误: {e}", i + 1),
+            Error::FailedQueries(n, total) => write!(f, "{total} 个查询中有 {n} 个有误"),
+            Error::Write(Some(path), e) => write!(f, "无法写入 {}: {e}", path.display()),
+            Error::Write(None, e) => write!(f, "无法写入结果: {e}"),
+        };
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return match self {
+            Error::Io(_, e) | Error::Write(_, e) => Some(e),
+            Error::Csv(e) => Some(e),
+            Error::Query(_, e) => Some(e),
+            _ => None,
+        };
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Error {
+        return Error::Csv(e);
+    }
+}
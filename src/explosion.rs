@@ -0,0 +1,168 @@
+use crate::common::*;
+use crate::joint::PosEvent;
+use std::ops::RangeInclusive;
+
+/// 落点列数的有效范围
+pub const COLS: RangeInclusive<f64> = 0.0..=10.0;
+
+/// 爆心横坐标的有效范围，为场地左右各加一列
+pub const CENTER_XS: RangeInclusive<i64> = -80..=880;
+
+/// 灰烬植物（及玉米炮）
+#[derive(strum::EnumString, strum::EnumIter, strum::Display, PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[strum(ascii_case_insensitive)]
+pub enum Explosion {
+    #[strum(to_string = "Cob", serialize = "CobCannon")]
+    Cob,
+    #[strum(to_string = "Cherry", serialize = "CherryBomb")]
+    Cherry,
+    #[strum(to_string = "Doom", serialize = "DoomShroom")]
+    Doom,
+    Jalapeno,
+}
+
+impl serde::Serialize for Explosion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Explosion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        return Explosion::from_str(&name)
+            .map_err(|_| serde::de::Error::custom(format!("未知的爆炸类型 {name}")));
+    }
+}
+
+/// 场景，决定行数和每行的纵坐标
+#[derive(strum::EnumString, strum::EnumIter, strum::Display, PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[strum(ascii_case_insensitive)]
+pub enum Scene {
+    #[strum(to_string = "Yard", serialize = "Day", serialize = "Night", serialize = "FrontYard")]
+    Yard,
+    #[strum(to_string = "Pool", serialize = "Fog")]
+    Pool,
+    #[strum(to_string = "Roof", serialize = "Moon")]
+    Roof,
+}
+
+impl serde::Serialize for Scene {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl Scene {
+    pub fn rows(self) -> i64 {
+        return if self == Scene::Pool {6} else {5};
+    }
+
+    /// 第 row 行（从 1 开始）在横坐标 x 处格子上边缘的纵坐标
+    pub fn row_y(self, row: i64, x: i64) -> i64 {
+        return match self {
+            Scene::Yard => 80 + 100 * (row - 1),
+            Scene::Pool => 80 + 85 * (row - 1),
+            // 屋顶左侧 5 列为斜坡，每向左 80px 下降 20px
+            Scene::Roof => 70 + 85 * (row - 1) + max(440i64.saturating_sub(x), 0) / 4,
+        };
+    }
+
+    /// 第 row 行取整坐标为 x 的僵尸的纵坐标
+    pub fn zombie_y(self, row: i64, x: i64) -> i64 {
+        return self.row_y(row, x + 40) - 30;
+    }
+
+    /// 落在第 row 行、爆心横坐标为 `center_x` 的爆炸的爆心纵坐标
+    pub fn center_y(self, row: i64, center_x: i64) -> i64 {
+        return self.row_y(row, center_x.saturating_sub(40)) + 40;
+    }
+}
+
+impl Explosion {
+    /// 爆炸半径，火爆辣椒为整行伤害，没有半径
+    pub fn radius(self) -> Option<i64> {
+        return match self {
+            Explosion::Cob | Explosion::Cherry => Some(115),
+            Explosion::Doom => Some(250),
+            Explosion::Jalapeno => None,
+        };
+    }
+
+    /// 对僵尸造成的伤害
+    pub fn damage(self) -> i32 {
+        return 1800;
+    }
+
+    /// 能波及的上下行数
+    pub fn row_range(self) -> i64 {
+        return match self {
+            Explosion::Cob | Explosion::Cherry => 1,
+            Explosion::Doom => 3,
+            Explosion::Jalapeno => 0,
+        };
+    }
+
+    /// 由落点列数（可为小数，如 8.8 列）得到爆心横坐标，列数应在 [`COLS`] 内，超出时结果没有意义但不会溢出
+    pub fn center_x(self, col: f64) -> i64 {
+        let x = (col * 80.0).round() as i64;
+        return match self {
+            // 9 列炮爆心为 685，与 README 示例一致
+            Explosion::Cob => x.saturating_sub(35),
+            // 植物 x 为 80 * col - 40，爆心在植物中心
+            Explosion::Cherry | Explosion::Doom | Explosion::Jalapeno => x,
+        };
+    }
+
+    /// 爆心横坐标为 `center_x` 时，能被同行爆炸命中的僵尸取整坐标范围，`None` 表示整行
+    pub fn hit_range(self, data: &ZombieData, center_x: i64) -> Option<(i64, i64)> {
+        // 圆与防御框 [x + def_x.0, x + def_x.1] 相交，爆心离场地很远时取到 i64 的边界
+        return self.radius().map(|radius|
+            (center_x.saturating_sub(radius).saturating_sub(i64::from(data.def_x.1)),
+             center_x.saturating_add(radius).saturating_sub(i64::from(data.def_x.0))));
+    }
+
+    /// 同行爆炸在 time 时命中僵尸的条件，用于联合查询
+    pub fn hit_event(self, data: &ZombieData, time: i64, center_x: i64) -> PosEvent {
+        // 整行伤害取一个足够大的范围，并限制在这一范围内，计算时不会溢出
+        const ROW: (i64, i64) = (i64::MIN / 4, i64::MAX / 4);
+        let range = self.hit_range(data, center_x).map_or(ROW, |(l, r)| (max(l, ROW.0), min(r, ROW.1)));
+        return PosEvent { time: time, range: range };
+    }
+
+    /// 同行爆炸命中僵尸的概率
+    pub fn hit_prob(self, data: &ZombieData, d: &PosDistribution, center_x: i64) -> f64 {
+        let Some((l, r)) = self.hit_range(data, center_x) else {
+            return 1.0;
+        };
+        return d.prob_range(l, r);
+    }
+
+    /// 落在 `scene` 第 `center_row` 行的爆炸是否命中第 row 行取整坐标为 x 的僵尸
+    pub fn hits(self, data: &ZombieData, scene: Scene, row: i64, x: i64, center_x: i64, center_row: i64) -> bool {
+        if (row - center_row).abs() > self.row_range() {
+            return false;
+        }
+        let Some(radius) = self.radius() else {
+            return true;
+        };
+        let (left, right) = (x + i64::from(data.def_x.0), x + i64::from(data.def_x.1));
+        let top = scene.zombie_y(row, x) + i64::from(data.def_y.0);
+        let bottom = scene.zombie_y(row, x) + i64::from(data.def_y.1);
+        let center_y = scene.center_y(center_row, center_x);
+        let dx = max(max(left.saturating_sub(center_x), center_x.saturating_sub(right)), 0);
+        let dy = max(max(top.saturating_sub(center_y), center_y.saturating_sub(bottom)), 0);
+        return dx.saturating_mul(dx).saturating_add(dy.saturating_mul(dy)) <= radius * radius;
+    }
+
+    /// 爆炸对每一行僵尸的命中概率，第 i 项对应第 i + 1 行
+    pub fn row_hit_probs(self, data: &ZombieData, d: &PosDistribution, scene: Scene,
+                         center_x: i64, center_row: i64) -> Vec<f64> {
+        return (1..=scene.rows()).map(|row| {
+            d.dist.iter().enumerate()
+                .filter(|&(i, _)| self.hits(data, scene, row, d.offset + i as i64, center_x, center_row))
+                .fold(0.0, |sum, (_, p)| sum + p)
+                .min(1.0)
+        }).collect();
+    }
+}
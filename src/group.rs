@@ -0,0 +1,65 @@
+use crate::common::*;
+
+// 所有分布覆盖的坐标范围 [first, last]
+fn span(group: &[&(&PosDistribution, u32)]) -> (i64, i64) {
+    let first = group.iter().map(|x| x.0.offset).min().unwrap_or(0);
+    let last = group.iter().map(|x| x.0.offset + x.0.dist.len() as i64 - 1).max().unwrap_or(-1);
+    return (first, last);
+}
+
+// 坐标 first 到 last 处的分布函数，cdf[i] 为取整坐标不大于 first + i 的概率
+fn cdf(d: &PosDistribution, first: i64, last: i64) -> Vec<f64> {
+    let mut sum = 0.0;
+    return (first..=last).map(|x| {
+        sum += d.prob(x);
+        f64::min(sum, 1.0)
+    }).collect();
+}
+
+fn from_cdf(cdf: &[f64], first: i64, min: f64, max: f64) -> PosDistribution {
+    let mut dist = Vec::with_capacity(cdf.len());
+    let mut prev = 0.0;
+    for &c in cdf {
+        dist.push(f64::max(c - prev, 0.0));
+        prev = f64::max(c, prev);
+    }
+    let mut result = PosDistribution { offset: first, dist: dist, min: min, max: max };
+    result.trim();
+    return result;
+}
+
+/// 多只相互独立的僵尸中最左一只的取整坐标分布，`group` 中每项为 (单只僵尸的分布, 数量)
+pub fn frontmost(group: &[(&PosDistribution, u32)]) -> PosDistribution {
+    let group: Vec<_> = group.iter().filter(|x| x.1 > 0).collect();
+    let (first, last) = span(&group);
+    let mut survive = vec![1.0; (last - first + 1) as usize];
+    for (d, count) in &group {
+        for (s, c) in survive.iter_mut().zip(cdf(d, first, last)) {
+            *s *= (1.0 - c).powf(f64::from(*count));
+        }
+    }
+    let cdf: Vec<f64> = survive.iter().map(|s| 1.0 - s).collect();
+    let min = group.iter().map(|x| x.0.min).fold(f64::INFINITY, f64::min);
+    let max = group.iter().map(|x| x.0.max).fold(f64::INFINITY, f64::min);
+    return from_cdf(&cdf, first, min, max);
+}
+
+/// 多只相互独立的僵尸中最右一只的取整坐标分布
+pub fn backmost(group: &[(&PosDistribution, u32)]) -> PosDistribution {
+    let group: Vec<_> = group.iter().filter(|x| x.1 > 0).collect();
+    let (first, last) = span(&group);
+    let mut all = vec![1.0; (last - first + 1) as usize];
+    for (d, count) in &group {
+        for (a, c) in all.iter_mut().zip(cdf(d, first, last)) {
+            *a *= c.powf(f64::from(*count));
+        }
+    }
+    let min = group.iter().map(|x| x.0.min).fold(f64::NEG_INFINITY, f64::max);
+    let max = group.iter().map(|x| x.0.max).fold(f64::NEG_INFINITY, f64::max);
+    return from_cdf(&all, first, min, max);
+}
+
+/// 所有僵尸的取整坐标都在 [l, r] 内的概率
+pub fn all_in_range(group: &[(&PosDistribution, u32)], l: i64, r: i64) -> f64 {
+    return group.iter().map(|(d, count)| d.prob_range(l, r).powf(f64::from(*count))).product();
+}
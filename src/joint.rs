@@ -0,0 +1,231 @@
+use crate::common::*;
+use crate::timeline::*;
+use crate::calculate_pos_distribution::{k_range, k_segments, constant_speed, zomboni_pos, Shifts};
+use crate::error::Error;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, ToPrimitive};
+use rayon::prelude::*;
+
+/// 联合查询的条件数上限，各种命中情况共 `2^MAX_EVENTS` 种
+pub const MAX_EVENTS: usize = 20;
+
+/// 联合查询中的一个条件：`time` 时取整坐标在 `range` 内（含两端）
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PosEvent {
+    pub time: i64,
+    pub range: (i64, i64),
+}
+
+// 一种情况：权重，以及去掉冻结后的各移动段，第 i 个目标时间恰好经过前 checkpoints[i] 段
+struct Path {
+    weight: Num,
+    runs: Vec<(MoveState, i64)>,
+    checkpoints: Vec<usize>,
+}
+
+impl Path {
+    // 按升序的目标时间切分各段，使每个目标时间都落在段的边界上
+    fn new(weight: Num, runs: &[(MoveState, i64)], times: &[i64]) -> Path {
+        let mut path = Path { weight: weight, runs: Vec::new(), checkpoints: Vec::new() };
+        let mut elapsed = 0;
+        let mut next = 0;
+        for &(state, len) in runs {
+            let mut start = elapsed;
+            while next < times.len() && times[next] <= elapsed + len {
+                let cut = max(times[next], start);
+                path.push(state, cut - start);
+                path.checkpoints.push(path.runs.len());
+                start = cut;
+                next += 1;
+            }
+            path.push(state, elapsed + len - start);
+            elapsed += len;
+        }
+        while path.checkpoints.len() < times.len() {
+            path.checkpoints.push(path.runs.len());
+        }
+        return path;
+    }
+
+    fn push(&mut self, state: MoveState, len: i64) {
+        if state == MoveState::Stopped || len == 0 {
+            return;
+        }
+        let at_checkpoint = self.checkpoints.last() == Some(&self.runs.len());
+        match self.runs.last_mut() {
+            Some(last) if last.0 == state && !at_checkpoint => last.1 += len,
+            _ => self.runs.push((state, len)),
+        }
+    }
+}
+
+// 出生点在 spawn 内均匀分布、u 在 [0, 1) 内均匀分布时，把每种条件成立情况的概率乘以 weight 加到 patterns 上，
+// 第 i 个时间的位移为 dx[i].0 + u * (dx[i].1 - dx[i].0)，第 i 个条件对应 patterns 下标的第 bits[i] 位
+fn add_patterns(spawn: (i64, i64), dx: &[(f64, f64)], events: &[PosEvent], bits: &[usize],
+                weight: f64, patterns: &mut [f64]) {
+    let weight = weight / (spawn.1 - spawn.0 + 1) as f64;
+    let mut intervals = Vec::with_capacity(events.len());
+    let mut points = Vec::with_capacity(events.len() * 2 + 2);
+    for s in spawn.0..=spawn.1 {
+        intervals.clear();
+        points.clear();
+        points.extend([0.0, 1.0]);
+        for (&(a, b), event) in dx.iter().zip(events) {
+            // 取整坐标 s - ceil(dx) 在 [l, r] 内即 dx 在 (s - r - 1, s - l] 内
+            let (dl, dr) = ((s - event.range.1 - 1) as f64, (s - event.range.0) as f64);
+            let (lo, hi) =
+                if b <= a { if dl < a && a <= dr {(0.0, 1.0)} else {(0.0, 0.0)} }
+                else { (((dl - a) / (b - a)).clamp(0.0, 1.0), ((dr - a) / (b - a)).clamp(0.0, 1.0)) };
+            intervals.push((lo, hi));
+            points.extend([lo, hi]);
+        }
+        points.sort_unstable_by(f64::total_cmp);
+        for lr in points.windows(2).filter(|lr| lr[0] < lr[1]) {
+            let mid = f64::midpoint(lr[0], lr[1]);
+            let mask = intervals.iter().zip(bits)
+                .filter(|(&(lo, hi), _)| lo <= mid && mid < hi)
+                .fold(0, |mask, (_, &bit)| mask | 1 << bit);
+            patterns[mask] += weight * (lr[1] - lr[0]);
+        }
+    }
+}
+
+fn joint_constant(data: &ZombieData, spawn: (i64, i64), paths: &[Path], events: &[PosEvent],
+                  bits: &[usize], patterns: &mut [f64]) -> Option<()> {
+    for path in paths {
+        let dx = path.checkpoints.iter().map(|&c| {
+            let dx = |speed: Num| -> Option<f64> {
+                let total = path.runs[..c].iter().try_fold(Num::from(0), |total, &(state, len)| {
+                    return total.checked_add(&constant_speed(speed, state)?.checked_mul(&Num::from(len))?);
+                })?;
+                return total.to_f64();
+            };
+            return Some((dx(data.speed.0)?, dx(data.speed.1)?));
+        }).collect::<Option<Vec<(f64, f64)>>>()?;
+        add_patterns(spawn, &dx, events, bits, path.weight.to_f64().unwrap(), patterns);
+    }
+    return Some(());
+}
+
+fn joint_animation(data: &ZombieData, spawn: (i64, i64), paths: &[Path], animation: &[Num],
+                   events: &[PosEvent], bits: &[usize], patterns: &mut [f64]) -> Option<()> {
+    let (k_min, k_max) = k_range(data, animation)?;
+    let timelines: Vec<Timeline> = paths.iter()
+        .map(|path| Timeline { weight: path.weight, runs: path.runs.clone() })
+        .collect();
+    let k_segments = k_segments(data, &timelines, animation)?;
+    let mut order: Vec<&Path> = paths.iter().collect();
+    order.sort_unstable_by(|a, b| a.runs.cmp(&b.runs));
+    let result = k_segments.par_windows(2).map(|lr| {
+        let (l, r) = (lr[0], lr[1]);
+        let shifts = Shifts::new(animation, l, r)?;
+        let segment_weight = if k_min == k_max {Num::new(1, 1)} else {r.checked_sub(&l)?.checked_div(&k_max.checked_sub(&k_min)?)?};
+        // 与上一种情况相同的前缀直接复用，第一个不同的段如果只是更长则从上一种情况接着算
+        let mut progress = vec![Shifts::start()];
+        let mut prev_runs: &[(MoveState, i64)] = &[];
+        let mut result = vec![0.0; patterns.len()];
+        for path in &order {
+            let common = prev_runs.iter().zip(path.runs.iter()).take_while(|(a, b)| a == b).count();
+            let extend = match (prev_runs.get(common), path.runs.get(common)) {
+                (Some(&(s0, len0)), Some(&(s1, len1))) if s0 == s1 && len0 <= len1 => Some((progress[common + 1], len0)),
+                _ => None,
+            };
+            progress.truncate(common + 1);
+            for (i, &(state, len)) in path.runs.iter().enumerate().skip(common) {
+                let next = match extend {
+                    Some((prev, prev_len)) if i == common => shifts.advance(prev, state, len - prev_len)?,
+                    _ => shifts.advance(progress[i], state, len)?,
+                };
+                progress.push(next);
+            }
+            prev_runs = &path.runs;
+            let dx: Vec<(f64, f64)> = path.checkpoints.iter()
+                .map(|&c| Shifts::dx(progress[c]))
+                .map(|(dx_min, dx_max)| (dx_min.to_f64().unwrap(), dx_max.to_f64().unwrap()))
+                .collect();
+            add_patterns(spawn, &dx, events, bits, product_f64(&[path.weight, segment_weight]), &mut result);
+        }
+        Some(result)
+    }).try_reduce(|| vec![0.0; patterns.len()], |a, b| Some(a.iter().zip(&b).map(|(x, y)| x + y).collect()))?;
+    for (p, x) in patterns.iter_mut().zip(result) {
+        *p += x;
+    }
+    return Some(());
+}
+
+fn joint_zomboni(spawn: (i64, i64), paths: &[Path], events: &[PosEvent], bits: &[usize], patterns: &mut [f64]) {
+    let spawn_span = (spawn.1 - spawn.0 + 1) as f64;
+    for path in paths {
+        let moved: Vec<i64> = path.checkpoints.iter().map(|&c| path.runs[..c].iter().map(|x| x.1).sum()).collect();
+        for spawn_x in spawn.0..=spawn.1 {
+            let mask = moved.iter().zip(events).zip(bits)
+                .filter(|((&time, event), _)| {
+                    let pos = zomboni_pos(spawn_x, time).floor() as i64;
+                    event.range.0 <= pos && pos <= event.range.1
+                })
+                .fold(0, |mask, (_, &bit)| mask | 1 << bit);
+            patterns[mask] += path.weight.to_f64().unwrap() / spawn_span;
+        }
+    }
+}
+
+/// 同一只僵尸各个条件成立情况的分布，第 mask 项为恰好满足 mask 中各位对应的条件的概率，`scenario.time` 不使用
+///
+/// 出生点、速度和冻结时间在各个时间之间是共享的，因此不能把各时间的概率直接相乘。
+///
+/// # Errors
+///
+/// 舞王秘籍僵尸的移动是随机游走，不支持联合查询，返回 [`Error::JointUnsupported`]；精确计算溢出时返回 [`Error::Overflow`]。
+/// `events` 多于 [`MAX_EVENTS`] 个时返回 [`Error::TooManyEvents`]。
+pub fn hit_patterns(data: &ZombieData, scenario: &Scenario, events: &[PosEvent]) -> Result<Vec<f64>, Error> {
+    if events.len() > MAX_EVENTS {
+        return Err(Error::TooManyEvents(events.len()));
+    }
+    let mut order: Vec<usize> = (0..events.len()).collect();
+    order.sort_by_key(|&i| events[i].time);
+    let sorted: Vec<PosEvent> = order.iter().map(|&i| events[i]).collect();
+    let mut patterns = vec![0.0; 1 << events.len()];
+    let Some(last) = sorted.last() else {
+        return Ok(vec![1.0]);
+    };
+    let scenario = Scenario { time: max(last.time, 0), ..scenario.clone() };
+    let spawn = if scenario.hugewave {data.spawn_hugewave} else {data.spawn};
+    let times: Vec<i64> = sorted.iter().map(|x| x.time).collect();
+    let timelines = build_timelines(data, &scenario).ok_or(Error::Overflow)?;
+    let paths: Vec<Path> = timelines.iter().map(|x| Path::new(x.weight, &x.runs, &times)).collect();
+    let done = match &data.movement_type {
+        MovementType::Constant => joint_constant(data, spawn, &paths, &sorted, &order, &mut patterns),
+        MovementType::Animation(x) => joint_animation(data, spawn, &paths, x, &sorted, &order, &mut patterns),
+        MovementType::Regular(a, b) => {
+            let done_a = joint_animation(data, spawn, &paths, a, &sorted, &order, &mut patterns);
+            let done_b = joint_animation(data, spawn, &paths, b, &sorted, &order, &mut patterns);
+            for x in &mut patterns {
+                *x /= 2.0;
+            }
+            done_a.and(done_b)
+        },
+        MovementType::DanceCheat => return Err(Error::JointUnsupported(scenario.zombie_type.to_string())),
+        MovementType::Dancing(x) => {
+            // 舞王进场在 299-310cs 间结束，减速时照常进场，被黄油时暂停，被冰后不再移动
+            let ice = first_freeze(data, &scenario);
+            let paths: Vec<Path> = (299..=310)
+                .map(|walk| Path::new(Num::new(1, 12), &timelines[0].dancing_walk(walk, ice).runs, &times))
+                .collect();
+            joint_animation(data, spawn, &paths, x, &sorted, &order, &mut patterns)
+        },
+        MovementType::Zomboni => {
+            joint_zomboni(spawn, &paths, &sorted, &order, &mut patterns);
+            Some(())
+        },
+    };
+    done.ok_or(Error::Overflow)?;
+    return Ok(patterns);
+}
+
+/// 同一只僵尸同时满足所有条件的概率，`scenario.time` 不使用
+///
+/// # Errors
+///
+/// 同 [`hit_patterns`]。
+pub fn joint_prob(data: &ZombieData, scenario: &Scenario, events: &[PosEvent]) -> Result<f64, Error> {
+    return hit_patterns(data, scenario, events).map(|x| x[x.len() - 1].min(1.0));
+}
@@ -0,0 +1,58 @@
+use crate::common::*;
+use crate::error::Error;
+use crate::explosion::Explosion;
+use crate::joint::{hit_patterns, PosEvent};
+
+/// 一次同行爆炸，命中时造成 `damage` 点伤害
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Strike {
+    pub time: i64,
+    pub explosion: Explosion,
+    pub center_x: i64,
+    pub damage: i32,
+}
+
+impl Strike {
+    /// 伤害为该爆炸默认伤害的爆炸
+    pub fn new(time: i64, explosion: Explosion, center_x: i64) -> Strike {
+        return Strike { time: time, explosion: explosion, center_x: center_x, damage: explosion.damage() };
+    }
+}
+
+/// 所有爆炸结束后僵尸受到的总伤害的分布，按伤害从小到大排列
+///
+/// 各次爆炸是否命中并不独立，这里按 [`hit_patterns`] 给出的联合分布计算。
+///
+/// # Errors
+///
+/// 同 [`hit_patterns`]，如舞王秘籍僵尸不支持联合查询，返回 [`Error::JointUnsupported`]。
+pub fn damage_distribution(data: &ZombieData, scenario: &Scenario, strikes: &[Strike]) -> Result<Vec<(i64, f64)>, Error> {
+    let events: Vec<PosEvent> = strikes.iter()
+        .map(|x| x.explosion.hit_event(data, x.time, x.center_x))
+        .collect();
+    let patterns = hit_patterns(data, scenario, &events)?;
+    let mut damage: Vec<(i64, f64)> = Vec::new();
+    for (mask, &p) in patterns.iter().enumerate().filter(|x| *x.1 > 0.0) {
+        let total = strikes.iter().enumerate()
+            .filter(|(i, _)| mask >> i & 1 == 1)
+            .map(|(_, x)| i64::from(x.damage))
+            .sum();
+        match damage.iter_mut().find(|x| x.0 == total) {
+            Some(x) => x.1 += p,
+            None => damage.push((total, p)),
+        }
+    }
+    damage.sort_unstable_by_key(|x| x.0);
+    return Ok(damage);
+}
+
+/// 所有爆炸结束后僵尸受到的伤害不低于其血量的概率
+///
+/// # Errors
+///
+/// 同 [`damage_distribution`]。
+pub fn kill_prob(data: &ZombieData, scenario: &Scenario, strikes: &[Strike]) -> Result<f64, Error> {
+    let damage = damage_distribution(data, scenario, strikes)?;
+    let prob = damage.iter().filter(|x| x.0 >= i64::from(data.hp)).fold(0.0, |sum, x| sum + x.1);
+    return Ok(prob.min(1.0));
+}
@@ -0,0 +1,42 @@
+//! 僵尸坐标分布计算器
+//!
+//! 给定僵尸类型、冰时机和目标时间，计算僵尸取整后横坐标的概率分布。
+//!
+//! ```no_run
+//! use zombie_movement_calculator::{ZombieDb, ZombieType, Scenario};
+//!
+//! let db = ZombieDb::builtin();
+//! let d = db.calculate(&Scenario::new(ZombieType::GigaGargantuar, 200))?;
+//! println!("{}", d.prob_range(0, 817));
+//! # Ok::<(), zombie_movement_calculator::Error>(())
+//! ```
+
+#![warn(clippy::pedantic)]
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::if_not_else,
+         clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss,
+         clippy::cast_possible_wrap, clippy::wildcard_imports, clippy::must_use_candidate)]
+
+pub mod common;
+pub mod calculate_pos_distribution;
+pub mod parse_data;
+pub mod batch;
+pub mod output;
+pub mod explosion;
+pub mod search;
+pub mod joint;
+pub mod kill;
+pub mod group;
+pub mod wave;
+pub mod table;
+pub mod error;
+pub mod validate;
+mod zombie_db;
+mod timeline;
+
+pub use common::{ZombieType, ZombieName, MovementType, ZombieData, Num, Scenario, Chill, IceSource, PosDistribution};
+pub use calculate_pos_distribution::{calculate_pos_distribution, calculate_series};
+pub use zombie_db::{ZombieDb, BUILTIN_DATA};
+pub use explosion::{Explosion, Scene};
+pub use joint::PosEvent;
+pub use kill::Strike;
+pub use error::Error;
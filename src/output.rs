@@ -0,0 +1,141 @@
+use crate::common::*;
+use crate::explosion::{Explosion, Scene};
+use crate::search::{ReachTimes, PassageDistribution};
+use crate::joint::PosEvent;
+use crate::kill::Strike;
+
+/// 单次查询的完整结果，用于 JSON 输出
+#[derive(serde::Serialize)]
+pub struct QueryReport<'a> {
+    pub scenario: &'a Scenario,
+    pub range: Option<(i64, i64)>,
+    pub prob: Option<f64>,
+    pub distribution: &'a PosDistribution,
+}
+
+/// 爆炸命中查询的结果
+#[derive(serde::Serialize)]
+pub struct HitReport<'a> {
+    pub scenario: &'a Scenario,
+    pub explosion: Explosion,
+    pub center_x: i64,
+    /// 能被命中的取整坐标范围，火爆辣椒为整行，此项为空
+    pub hit_range: Option<(i64, i64)>,
+    pub prob: f64,
+}
+
+/// 按行的爆炸命中查询结果，`rows[i]` 为第 i + 1 行的命中概率
+#[derive(serde::Serialize)]
+pub struct RowHitReport<'a> {
+    pub scenario: &'a Scenario,
+    pub explosion: Explosion,
+    pub scene: Scene,
+    pub center_x: i64,
+    pub center_row: i64,
+    pub rows: Vec<f64>,
+}
+
+/// 到达时间查询的结果，`scenario.time` 为搜索范围的右端
+#[derive(serde::Serialize)]
+pub struct ReachReport<'a> {
+    pub scenario: &'a Scenario,
+    pub x: i64,
+    pub threshold: f64,
+    pub window: (i64, i64),
+    pub times: ReachTimes,
+    /// 每个时间的到达概率，未要求时为空
+    pub curve: Option<Vec<(i64, f64)>>,
+}
+
+/// 最佳爆炸时间查询的结果，`scenario.time` 为搜索范围的右端
+#[derive(serde::Serialize)]
+pub struct TimingReport<'a> {
+    pub scenario: &'a Scenario,
+    pub explosion: Explosion,
+    pub center_x: i64,
+    pub window: (i64, i64),
+    /// 命中概率最大的时间及其概率
+    pub best: Option<(i64, f64)>,
+    pub threshold: Option<f64>,
+    /// 命中概率不低于 `threshold` 的时间区间
+    pub ranges: Option<Vec<(i64, i64)>>,
+    pub curve: Vec<(i64, f64)>,
+}
+
+/// 联合查询的结果，`marginals[i]` 为只考虑第 i 个条件时的概率
+#[derive(serde::Serialize)]
+pub struct JointReport<'a> {
+    pub scenario: &'a Scenario,
+    pub events: &'a [PosEvent],
+    pub marginals: Vec<f64>,
+    pub joint: f64,
+}
+
+/// 死亡概率查询的结果，`damage` 为总伤害的分布
+#[derive(serde::Serialize)]
+pub struct KillReport<'a> {
+    pub scenario: &'a Scenario,
+    pub hp: i32,
+    pub strikes: &'a [Strike],
+    pub damage: Vec<(i64, f64)>,
+    pub kill: f64,
+}
+
+/// 多只僵尸查询的结果，第 i 种僵尸的查询参数为 `scenarios[i]`，数量为 `counts[i]`
+#[derive(serde::Serialize)]
+pub struct GroupReport<'a> {
+    pub scenarios: &'a [Scenario],
+    pub counts: &'a [u32],
+    pub range: Option<(i64, i64)>,
+    /// 所有僵尸都在 `range` 内的概率
+    pub all_in_range: Option<f64>,
+    pub frontmost: &'a PosDistribution,
+    pub backmost: &'a PosDistribution,
+}
+
+/// 一波中某种僵尸的占比和期望数量
+#[derive(serde::Serialize)]
+pub struct WaveEntry {
+    pub zombie_type: ZombieName,
+    pub prob: f64,
+    pub expected: f64,
+}
+
+/// 整波僵尸查询的结果，`scenario.zombie_type` 不使用
+#[derive(serde::Serialize)]
+pub struct WaveReport<'a> {
+    pub scenario: &'a Scenario,
+    pub count: u32,
+    pub composition: Vec<WaveEntry>,
+    pub range: Option<(i64, i64)>,
+    /// 整波僵尸都在 `range` 内的概率
+    pub all_in_range: Option<f64>,
+    /// 最左一只僵尸的坐标分布
+    pub frontmost: &'a PosDistribution,
+}
+
+/// 首次到达时间查询的结果，`scenario.time` 为搜索范围的右端
+#[derive(serde::Serialize)]
+pub struct PassageReport<'a> {
+    pub scenario: &'a Scenario,
+    pub x: i64,
+    pub window: (i64, i64),
+    pub distribution: &'a PassageDistribution,
+}
+
+/// 以 `x,prob` 两列输出分布中的非零项
+///
+/// # Errors
+///
+/// 写入失败时返回 [`csv::Error`]。
+pub fn write_dist_csv<W: std::io::Write>(writer: W, d: &PosDistribution) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["x", "prob"])?;
+    if let Some((first, last)) = d.support(0.0) {
+        for (x, prob) in (first..=last).zip(d.slice(first, last)) {
+            csv_writer.serialize((x, prob))?;
+        }
+    }
+    csv_writer.flush()?;
+    return Ok(());
+}
@@ -1,8 +1,12 @@
 use crate::common::*;
+use crate::error::Error;
+use crate::validate::{self, Severity};
+use num_traits::CheckedSub;
 
 #[derive(serde::Deserialize)]
-struct RawZombieData {
-    name: String,
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct RawZombieData {
+    pub(crate) name: String,
     spawn_l: i64,
     spawn_r: i64,
     spawn_hugewave_l: i64,
@@ -27,50 +31,72 @@ struct RawZombieData {
     in_pool_wave1to5: bool
 }
 
-fn decimal_to_rational(decimal: &str) -> Num {
-    return match decimal.find('.') {
-        Some(pos) => {
-            let negative = decimal.starts_with('-');
-            let int_part = decimal[negative.into()..pos].parse::<i64>().unwrap();
-            let frac_part = decimal[pos+1..].parse::<i64>().unwrap();
-            let den = i64::pow(10, (decimal.len() - pos - 1) as u32);
-            let num = (if negative {-1} else {1}) * (int_part * den + frac_part);
-            Num::new(num, den)
-        },
-        None => decimal.parse::<Num>().unwrap()
+// 小数点前后都必须是数字，只允许开头有负号，超出 i64 范围时返回 [`Error::Overflow`]
+fn decimal_to_rational(decimal: &str) -> Result<Num, Error> {
+    let invalid = || Error::InvalidNumber(decimal.to_string());
+    let decimal = decimal.trim();
+    let Some((int_part, frac_part)) = decimal.split_once('.') else {
+        return decimal.parse::<Num>().map_err(|_| invalid());
+    };
+    let (negative, int_part) = match int_part.strip_prefix('-') {
+        Some(x) => (true, x),
+        None => (false, int_part),
     };
+    let is_digits = |x: &str| !x.is_empty() && x.bytes().all(|c| c.is_ascii_digit());
+    if !is_digits(int_part) || !is_digits(frac_part) {
+        return Err(invalid());
+    }
+    // 末尾的 0 不影响数值，去掉后小数位数更少
+    let frac_part = frac_part.trim_end_matches('0');
+    // 只含数字时解析失败只可能是超出范围
+    let int_part = int_part.parse::<i64>().map_err(|_| Error::Overflow)?;
+    let (den, frac_part) = match frac_part {
+        "" => (1, 0),
+        x => (u32::try_from(x.len()).ok().and_then(|len| 10i64.checked_pow(len)).ok_or(Error::Overflow)?,
+              x.parse::<i64>().map_err(|_| Error::Overflow)?),
+    };
+    let num = int_part.checked_mul(den).and_then(|x| x.checked_add(frac_part)).ok_or(Error::Overflow)?;
+    return Ok(Num::new(if negative {-num} else {num}, den));
 }
 
-fn parse_animation(raw: &str) -> Vec<Num> {
-    let nums = raw.split(',').map(decimal_to_rational).collect::<Vec<_>>();
+fn parse_animation(raw: &str) -> Result<Vec<Num>, Error> {
+    let nums = raw.split(',').map(decimal_to_rational).collect::<Result<Vec<_>, _>>()?;
     return nums.windows(2)
-               .map(|x| x[1] - x[0])
-               .collect::<Vec<_>>();
+               .map(|x| x[1].checked_sub(&x[0]).ok_or(Error::Overflow))
+               .collect();
 }
 
-fn parse_regular(raw: &str) -> (Vec<Num>, Vec<Num>) {
-    let sep = raw.find(';').unwrap();
-    return (parse_animation(&raw[..sep]), parse_animation(&raw[sep+1..]));
+fn parse_regular(raw: &str) -> Result<(Vec<Num>, Vec<Num>), String> {
+    let sep = raw.find(';').ok_or("regular 需要两条用 ; 分隔的动画轨道")?;
+    let parse = |x| parse_animation(x).map_err(|e| e.to_string());
+    return Ok((parse(&raw[..sep])?, parse(&raw[sep+1..])?));
 }
 
-fn convert_zombie_data(data: RawZombieData) -> ZombieData {
-    let movement_type = match data.movement_type.as_str() {
-        "constant" => MovementType::Constant,
-        "animation" => MovementType::Animation(parse_animation(&data.movement_args.unwrap())),
+fn parse_movement(data: &RawZombieData) -> Result<MovementType, String> {
+    let args = || data.movement_args.as_deref().ok_or(format!("{} 需要 movement_args", data.movement_type));
+    let animation = |raw| parse_animation(raw).map_err(|e| e.to_string());
+    return match data.movement_type.as_str() {
+        "constant" => Ok(MovementType::Constant),
+        "animation" => Ok(MovementType::Animation(animation(args()?)?)),
         "regular" => {
-            let tmp = parse_regular(&data.movement_args.unwrap());
-            MovementType::Regular(tmp.0, tmp.1)
+            let tmp = parse_regular(args()?)?;
+            Ok(MovementType::Regular(tmp.0, tmp.1))
         },
-        "dancecheat" => MovementType::DanceCheat,
-        "dancing" => MovementType::Dancing(parse_animation(&data.movement_args.unwrap())),
-        "zomboni" => MovementType::Zomboni,
-        _ => unreachable!()
+        "dancecheat" => Ok(MovementType::DanceCheat),
+        "dancing" => Ok(MovementType::Dancing(animation(args()?)?)),
+        "zomboni" => Ok(MovementType::Zomboni),
+        other => Err(format!("未知的移动方式 {other}"))
     };
-    return ZombieData {
+}
+
+pub(crate) fn convert_zombie_data(data: &RawZombieData) -> Result<ZombieData, Error> {
+    let movement_type = parse_movement(data)
+        .map_err(|reason| Error::BadMovement { zombie: data.name.clone(), reason: reason })?;
+    return Ok(ZombieData {
         spawn: (data.spawn_l, data.spawn_r),
         spawn_hugewave: (data.spawn_hugewave_l, data.spawn_hugewave_r),
         movement_type: movement_type,
-        speed: (decimal_to_rational(&data.speed_l), decimal_to_rational(&data.speed_r)),
+        speed: (decimal_to_rational(&data.speed_l)?, decimal_to_rational(&data.speed_r)?),
         freeze_immune: data.freeze_immune,
         chill_immune: data.chill_immune,
         def_x: (data.defx_l, data.defx_r),
@@ -81,13 +107,37 @@ fn convert_zombie_data(data: RawZombieData) -> ZombieData {
         summon_weight_hugewave: data.summon_weight_hugewave,
         if_generate_in: (data.on_ground, data.in_pool),
         if_generate_in_wave1to5: (data.on_ground, data.in_pool_wave1to5)
-    };
+    });
+}
+
+/// 按文件中的顺序读取 CSV 的每一行，不做检查
+pub(crate) fn read_rows(file_content: &[u8]) -> csv::DeserializeRecordsIntoIter<&[u8], RawZombieData> {
+    return csv::Reader::from_reader(file_content).into_deserialize();
 }
 
-pub fn get_zombie_db(file_content: &[u8]) -> HashMap<ZombieType, ZombieData> {
-    let mut csv_reader = csv::Reader::from_reader(file_content);
-    return csv_reader.deserialize::<RawZombieData>()
-                     .map(Result::unwrap)
-                     .map(|x| (ZombieType::from_str(&x.name).expect(&x.name), convert_zombie_data(x)))
-                     .collect();
+/// 按文件中的顺序读取所有僵尸，名字不是内置僵尸类型时视为自定义僵尸
+///
+/// # Errors
+///
+/// CSV 格式有误时返回 [`Error::Csv`]；数字无法解析或超出范围时返回 [`Error::InvalidNumber`] 或 [`Error::Overflow`]；
+/// 动画轨道有误时返回 [`Error::BadMovement`]；名字为空或数据有误（见 [`validate::check_zombie_data`]）时返回 [`Error::BadData`]；
+/// 名字重复时返回 [`Error::DuplicateZombie`]。
+pub fn get_zombie_db(file_content: &[u8]) -> Result<Vec<(ZombieName, ZombieData)>, Error> {
+    let mut db: Vec<(ZombieName, ZombieData)> = Vec::new();
+    for x in read_rows(file_content) {
+        let x = x?;
+        if x.name.trim().is_empty() {
+            return Err(Error::BadData { zombie: x.name, reason: "僵尸名字不能为空".to_string() });
+        }
+        let name = ZombieName::new(&x.name);
+        let data = convert_zombie_data(&x)?;
+        if let Some((_, reason)) = validate::check_zombie_data(&data).into_iter().find(|issue| issue.0 == Severity::Error) {
+            return Err(Error::BadData { zombie: x.name, reason: reason });
+        }
+        if db.iter().any(|(other, _)| other.key() == name.key()) {
+            return Err(Error::DuplicateZombie(x.name));
+        }
+        db.push((name, data));
+    }
+    return Ok(db);
 }
@@ -0,0 +1,176 @@
+use crate::common::*;
+use crate::zombie_db::ZombieDb;
+use crate::explosion::Explosion;
+use crate::calculate_pos_distribution::calculate_pos_distribution;
+use crate::error::Error;
+
+/// 坐标阈值的首末到达时间，`None` 表示在搜索范围内不存在
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReachTimes {
+    /// 取整坐标不大于 x 的概率超过阈值的最早时间
+    pub first: Option<i64>,
+    /// 取整坐标大于 x 的概率超过阈值的最晚时间
+    pub last: Option<i64>,
+}
+
+/// 首次到达时间的分布，`dist[i]` 为取整坐标恰好在 `offset + i` 时第一次不大于 x 的概率
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct PassageDistribution {
+    pub offset: i64,
+    pub dist: Vec<f64>,
+    /// 搜索范围结束时仍未到达的概率
+    pub remaining: f64,
+}
+
+/// 在 `scenario.time` 时取整坐标不大于 x 的概率
+///
+/// # Errors
+///
+/// 同 [`ZombieDb::calculate`]。
+pub fn reach_prob(db: &ZombieDb, scenario: &Scenario, x: i64) -> Result<f64, Error> {
+    return Ok(db.calculate(scenario)?.prob_range(i64::MIN, x));
+}
+
+// 已经检查过僵尸类型和时间，见 check_window
+fn prob_at(data: &ZombieData, scenario: &Scenario, x: i64, time: i64) -> Result<f64, Error> {
+    return Ok(calculate_pos_distribution(data, &Scenario { time: time, ..scenario.clone() })?.prob_range(i64::MIN, x));
+}
+
+// 时间范围不为空时左端不能为负数，右端不小于左端
+fn check_window(window: (i64, i64)) -> Result<(), Error> {
+    if window.0 <= window.1 && window.0 < 0 {
+        return Err(Error::InvalidTime(window.0));
+    }
+    return Ok(());
+}
+
+// [l, r] 内使 pred 成立的最小时间，要求 pred 关于时间单调
+// 先从 l 开始倍增步长找到成立的位置再二分，避免计算远超所需的时间
+// pred 出错时直接返回错误
+fn first_true(l: i64, r: i64, pred: impl Fn(i64) -> Result<bool, Error>) -> Result<Option<i64>, Error> {
+    if l > r {
+        return Ok(None);
+    }
+    let (mut lo, mut step) = (l, 1);
+    let mut hi = loop {
+        let t = min(lo + step - 1, r);
+        if pred(t)? {
+            break t;
+        }
+        if t == r {
+            return Ok(None);
+        }
+        lo = t + 1;
+        step *= 2;
+    };
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid)? {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    return Ok(Some(hi));
+}
+
+/// 在时间范围 `window` 内查找到达坐标 x 的首末时间，忽略 `scenario.time`
+///
+/// 僵尸只会向左移动时到达概率关于时间单调不减，可以二分查找；会向右移动的僵尸（见 [`MovementType::is_monotone`]）
+/// 逐个计算范围内的每个时间。
+///
+/// # Errors
+///
+/// 时间范围不为空且左端为负数时返回 [`Error::InvalidTime`]，其余同 [`ZombieDb::calculate`]。
+pub fn reach_times(db: &ZombieDb, scenario: &Scenario, x: i64, threshold: f64, window: (i64, i64)) -> Result<ReachTimes, Error> {
+    let data = db.get(&scenario.zombie_type)?;
+    check_window(window)?;
+    if !data.movement_type.is_monotone() {
+        let curve = reach_curve(db, scenario, x, window, 1)?;
+        return Ok(ReachTimes {
+            first: curve.iter().find(|x| x.1 > threshold).map(|x| x.0),
+            last: curve.iter().rfind(|x| 1.0 - x.1 > threshold).map(|x| x.0),
+        });
+    }
+    let (first, last) = rayon::join(
+        || first_true(window.0, window.1, |t| Ok(prob_at(data, scenario, x, t)? > threshold)),
+        || first_true(window.0, window.1, |t| Ok(1.0 - prob_at(data, scenario, x, t)? <= threshold)),
+    );
+    let (first, last) = (first?, last?);
+    let last = last.map_or(Some(window.1), |t| if t > window.0 {Some(t - 1)} else {None});
+    return Ok(ReachTimes { first: first, last: last });
+}
+
+/// 时间范围 `window` 内每隔 `step` 的到达概率
+///
+/// # Errors
+///
+/// 同 [`ZombieDb::calculate_series`]。
+pub fn reach_curve(db: &ZombieDb, scenario: &Scenario, x: i64, window: (i64, i64), step: i64) -> Result<Vec<(i64, f64)>, Error> {
+    let times: Vec<i64> = (window.0..=window.1).step_by(max(step, 1) as usize).collect();
+    let dists = db.calculate_series(scenario, &times)?;
+    return Ok(times.iter().zip(&dists).map(|(&t, d)| (t, d.prob_range(i64::MIN, x))).collect());
+}
+
+/// 时间范围 `window` 内取整坐标第一次不大于 x 的时间分布，在 `window.0` 之前已到达的概率计入第一项
+///
+/// 僵尸不会向右移动，因此首次到达时间不超过 t 的概率等于 t 时已到达的概率，
+/// 只需二分出到达概率从 0 变为 1 的时间段，再计算这段时间内每个时间的到达概率。
+///
+/// # Errors
+///
+/// 可能向右移动的僵尸（见 [`MovementType::is_monotone`]）到达后可能退回，返回 [`Error::NotMonotone`]；
+/// 时间范围有误时同 [`reach_times`]，其余同 [`ZombieDb::calculate`]。
+pub fn first_passage(db: &ZombieDb, scenario: &Scenario, x: i64, window: (i64, i64)) -> Result<PassageDistribution, Error> {
+    let data = db.get(&scenario.zombie_type)?;
+    check_window(window)?;
+    if !data.movement_type.is_monotone() {
+        return Err(Error::NotMonotone(scenario.zombie_type.to_string()));
+    }
+    let Some(first) = first_true(window.0, window.1, |t| Ok(prob_at(data, scenario, x, t)? > 0.0))? else {
+        return Ok(PassageDistribution { offset: window.0, dist: Vec::new(), remaining: 1.0 });
+    };
+    let last = first_true(first, window.1, |t| Ok(prob_at(data, scenario, x, t)? >= 1.0 - 1e-12))?.unwrap_or(window.1);
+    let times: Vec<i64> = (first..=last).collect();
+    let cdf: Vec<f64> = db.calculate_series(scenario, &times)?.iter().map(|d| d.prob_range(i64::MIN, x)).collect();
+    let mut dist = Vec::with_capacity(cdf.len());
+    let mut prev = 0.0;
+    for &p in &cdf {
+        dist.push(f64::max(p - prev, 0.0));
+        prev = f64::max(p, prev);
+    }
+    return Ok(PassageDistribution { offset: first, dist: dist, remaining: f64::max(1.0 - prev, 0.0) });
+}
+
+/// 时间范围 `window` 内每个时间的同行爆炸命中概率
+///
+/// # Errors
+///
+/// 同 [`ZombieDb::calculate_series`]。
+pub fn hit_curve(db: &ZombieDb, scenario: &Scenario, explosion: Explosion, center_x: i64,
+                 window: (i64, i64)) -> Result<Vec<(i64, f64)>, Error> {
+    let data = db.get(&scenario.zombie_type)?;
+    let times: Vec<i64> = (window.0..=window.1).collect();
+    let dists = db.calculate_series(scenario, &times)?;
+    return Ok(times.iter().zip(&dists).map(|(&t, d)| (t, explosion.hit_prob(data, d, center_x))).collect());
+}
+
+/// 概率最大的时间，有多个时取最早的
+pub fn best_time(curve: &[(i64, f64)]) -> Option<(i64, f64)> {
+    return curve.iter().copied().fold(None, |best, (t, p)| match best {
+        Some((_, best_p)) if best_p >= p => best,
+        _ => Some((t, p)),
+    });
+}
+
+/// 概率不低于 threshold 的时间，连续的时间合并为区间
+pub fn time_ranges(curve: &[(i64, f64)], threshold: f64) -> Vec<(i64, i64)> {
+    let mut ranges: Vec<(i64, i64)> = Vec::new();
+    for &(t, _) in curve.iter().filter(|x| x.1 >= threshold) {
+        match ranges.last_mut() {
+            Some(last) if last.1 + 1 == t => last.1 = t,
+            _ => ranges.push((t, t)),
+        }
+    }
+    return ranges;
+}
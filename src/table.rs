@@ -0,0 +1,84 @@
+use crate::common::*;
+use crate::zombie_db::ZombieDb;
+use crate::error::Error;
+use rayon::prelude::*;
+
+/// 坐标表中的一行，`quantiles[i]` 为第 i 个分位数对应的取整坐标
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct TableRow {
+    pub zombie: ZombieName,
+    pub time: i64,
+    pub min: f64,
+    pub max: f64,
+    pub quantiles: Vec<i64>,
+}
+
+/// 所有僵尸在时间范围 `window` 内每隔 `step` 的坐标表，冰、减速等取自 `scenario`，忽略其中的僵尸类型和目标时间
+///
+/// 结果按僵尸、时间排序。
+///
+/// # Errors
+///
+/// 任一僵尸的计算出错时返回该错误，见 [`ZombieDb::calculate_series`]。
+pub fn coordinate_table(db: &ZombieDb, zombies: &[ZombieName], scenario: &Scenario, window: (i64, i64),
+                        step: i64, quantiles: &[f64]) -> Result<Vec<TableRow>, Error> {
+    let times: Vec<i64> = (window.0..=window.1).step_by(max(step, 1) as usize).collect();
+    let dists = zombies.par_iter()
+        .map(|zombie| db.calculate_series(&Scenario { zombie_type: zombie.clone(), ..scenario.clone() }, &times))
+        .collect::<Result<Vec<_>, Error>>()?;
+    return Ok(zombies.iter().zip(dists).flat_map(|(zombie, dists)| {
+        times.iter().zip(dists).map(move |(&time, d)| TableRow {
+            zombie: zombie.clone(),
+            time: time,
+            min: d.min,
+            max: d.max,
+            quantiles: quantiles.iter().map(|&p| d.quantile(p)).collect(),
+        })
+    }).collect());
+}
+
+// 表头，分位数列名为 p 加百分数，如 p50、p99.9，百分数最多保留六位小数以免出现 p5.000000000000001
+fn header(quantiles: &[f64]) -> Vec<String> {
+    let mut result: Vec<String> = ["zombie", "time", "min", "max"].iter().map(ToString::to_string).collect();
+    result.extend(quantiles.iter().map(|p| {
+        let percent = format!("{:.6}", p * 100.0);
+        format!("p{}", percent.trim_end_matches('0').trim_end_matches('.'))
+    }));
+    return result;
+}
+
+fn record(row: &TableRow) -> Vec<String> {
+    let mut result = vec![row.zombie.to_string(), row.time.to_string(), format!("{:.3}", row.min), format!("{:.3}", row.max)];
+    result.extend(row.quantiles.iter().map(ToString::to_string));
+    return result;
+}
+
+/// 以 CSV 输出坐标表，精确坐标保留三位小数
+///
+/// # Errors
+///
+/// 写入失败时返回 [`csv::Error`]。
+pub fn write_table_csv<W: std::io::Write>(writer: W, quantiles: &[f64], rows: &[TableRow]) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(header(quantiles))?;
+    for row in rows {
+        csv_writer.write_record(record(row))?;
+    }
+    csv_writer.flush()?;
+    return Ok(());
+}
+
+/// 以 Markdown 表格输出坐标表
+///
+/// # Errors
+///
+/// 写入失败时返回 [`std::io::Error`]。
+pub fn write_table_markdown<W: std::io::Write>(mut writer: W, quantiles: &[f64], rows: &[TableRow]) -> std::io::Result<()> {
+    let header = header(quantiles);
+    writeln!(writer, "| {} |", header.join(" | "))?;
+    writeln!(writer, "|{}", " --- |".repeat(header.len()))?;
+    for row in rows {
+        writeln!(writer, "| {} |", record(row).join(" | "))?;
+    }
+    return Ok(());
+}
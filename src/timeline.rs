@@ -0,0 +1,179 @@
+use crate::common::*;
+use std::collections::hash_map::Entry;
+use num_traits::{CheckedAdd, CheckedDiv};
+
+/// 僵尸在某一 cs 内的移动状态
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum MoveState {
+    Normal,
+    Chilled,
+    /// 冻结，不移动且动画不推进
+    Stopped,
+}
+
+/// 目标时间前每 cs 移动状态的一种可能情况，`runs` 为按时间顺序排列的 (状态, 持续时间)
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    pub weight: Num,
+    pub runs: Vec<(MoveState, i64)>,
+}
+
+impl Timeline {
+    pub fn count(&self, state: MoveState) -> i64 {
+        return self.runs.iter().filter(|x| x.0 == state).map(|x| x.1).sum();
+    }
+
+    /// 去掉冻结段后的各段，冻结不影响位移和动画相位
+    pub fn moving_runs(&self) -> Vec<(MoveState, i64)> {
+        let mut runs: Vec<(MoveState, i64)> = Vec::new();
+        for &(state, len) in self.runs.iter().filter(|x| x.0 != MoveState::Stopped) {
+            match runs.last_mut() {
+                Some(last) if last.0 == state => last.1 += len,
+                _ => runs.push((state, len)),
+            }
+        }
+        return runs;
+    }
+
+    /// 舞王进场时的移动状态：未冻结的时间累计到 `walk` cs 后进场结束，`ice` 时被冰冻结后不再移动，减速时照常进场，
+    /// 黄油只使进场暂停，之后均为冻结，权重为 1
+    pub fn dancing_walk(&self, walk: i64, ice: Option<i64>) -> Timeline {
+        let total: i64 = self.runs.iter().map(|x| x.1).sum();
+        // 第 ice cs 起冻结
+        let end = ice.map_or(total, |t| min(t - 1, total));
+        let mut runs: Vec<(MoveState, i64)> = Vec::new();
+        let (mut elapsed, mut walked) = (0, 0);
+        for &(state, len) in &self.runs {
+            let len = if state == MoveState::Stopped { min(len, end - elapsed) }
+                else { min(len, min(end - elapsed, walk - walked)) };
+            if len <= 0 {
+                break;
+            }
+            runs.push((state, len));
+            elapsed += len;
+            if state != MoveState::Stopped {
+                walked += len;
+            }
+        }
+        match runs.last_mut() {
+            Some(last) if last.0 == MoveState::Stopped => last.1 += total - elapsed,
+            _ if total > elapsed => runs.push((MoveState::Stopped, total - elapsed)),
+            _ => {},
+        }
+        return Timeline { weight: Num::new(1, 1), runs: runs };
+    }
+}
+
+/// 第一次冻结僵尸的冰的生效时间，不会被冻结时为 `None`
+pub fn first_freeze(data: &ZombieData, scenario: &Scenario) -> Option<i64> {
+    if data.freeze_immune || data.chill_immune {
+        return None;
+    }
+    return scenario.effective_ice_times().into_iter().filter(|&t| t > 0).min();
+}
+
+type Runs = Vec<(MoveState, i64)>;
+
+// 一种情况下的冻结区间（也用于黄油），区间均为左闭右开，第 t cs 受冰影响表示前 t - 1 cs 为原速
+struct Branch {
+    weight: Num,
+    freezes: Vec<(i64, i64)>,
+}
+
+/// 枚举目标时间前所有可能的移动状态序列，相同的序列会被合并
+///
+/// 未减速的僵尸被冰冻结 400-600cs（均匀分布），减速或冻结中的僵尸被冰时冻结时间重置为 300cs；
+/// 每次冰都使减速持续到冰后 2000cs，寒冰射手等的每次命中使减速持续到命中后 1000cs；
+/// 黄油使僵尸停止 400cs，不会使僵尸减速。权重的分母超出 [`Num`] 的范围时返回 `None`。
+pub fn build_timelines(data: &ZombieData, scenario: &Scenario) -> Option<Vec<Timeline>> {
+    let time = scenario.time;
+    let mut ice_times: Vec<i64> = scenario.effective_ice_times().into_iter().filter(|&t| t > 0 && t <= time).collect();
+    let mut chills: Vec<(i64, i64)> = scenario.chills.iter()
+        .flat_map(|chill| chill.windows(time))
+        .collect();
+    if data.chill_immune {
+        ice_times.clear();
+        chills.clear();
+    }
+    ice_times.sort_unstable();
+    ice_times.dedup();
+    let butters: Vec<(i64, i64)> = scenario.butter_times.iter()
+        .filter(|&&t| t > 0 && t <= time)
+        .map(|&t| (t, t.saturating_add(399)))
+        .collect();
+    let mut branches = vec![Branch { weight: Num::new(1, 1), freezes: Vec::new() }];
+    for &t in &ice_times {
+        let cold = chills.iter().any(|&(l, r)| l <= t && t < r);
+        chills.push((t, t.saturating_add(1999)));
+        if data.freeze_immune {
+            continue;
+        }
+        branches = branches.into_iter().map(|branch| -> Option<Vec<Branch>> {
+            if cold {
+                let mut freezes = branch.freezes;
+                if let Some(last) = freezes.last_mut() {
+                    last.1 = min(last.1, t);
+                }
+                freezes.push((t, t.saturating_add(299)));
+                Some(vec![Branch { weight: branch.weight, freezes: freezes }])
+            } else {
+                let weight = branch.weight.checked_div(&Num::from(201))?;
+                Some((400..=600).map(|freeze| {
+                    let mut freezes = branch.freezes.clone();
+                    // 目标时间之后的部分不影响结果，截断后可以合并
+                    freezes.push((t, min(t.saturating_add(freeze - 1), time.saturating_add(1))));
+                    Branch { weight: weight, freezes: freezes }
+                }).collect())
+            }
+        }).collect::<Option<Vec<_>>>()?.into_iter().flatten().collect();
+        branches = merge_branches(branches, &butters, &chills, t)?;
+    }
+    let mut merged: HashMap<Vec<(MoveState, i64)>, Num> = HashMap::new();
+    for mut branch in branches {
+        branch.freezes.extend_from_slice(&butters);
+        let weight = merged.entry(to_runs(&branch.freezes, &chills, time)).or_insert(Num::new(0, 1));
+        *weight = weight.checked_add(&branch.weight)?;
+    }
+    let mut result: Vec<Timeline> = merged.into_iter().map(|(runs, weight)| Timeline { weight: weight, runs: runs }).collect();
+    result.sort_unstable_by(|a, b| a.runs.cmp(&b.runs));
+    return Some(result);
+}
+
+// 此时只有最后一个冻结区间可能持续到 t 之后，t 之前的状态序列和最后一个冻结区间相同的情况之后也相同，
+// 合并后每次冰的分支数不会随冰的次数指数增长
+fn merge_branches(branches: Vec<Branch>, butters: &[(i64, i64)], chills: &[(i64, i64)], t: i64) -> Option<Vec<Branch>> {
+    let mut merged: HashMap<(Runs, (i64, i64)), Branch> = HashMap::new();
+    for branch in branches {
+        // 调用前刚加入了 t 时的冻结区间
+        let (&last, settled) = branch.freezes.split_last().unwrap();
+        let stops: Vec<(i64, i64)> = settled.iter().chain(butters).copied().collect();
+        match merged.entry((to_runs(&stops, chills, t - 1), last)) {
+            Entry::Occupied(mut entry) => entry.get_mut().weight = entry.get().weight.checked_add(&branch.weight)?,
+            Entry::Vacant(entry) => { entry.insert(branch); },
+        }
+    }
+    return Some(merged.into_values().collect());
+}
+
+fn to_runs(stops: &[(i64, i64)], chills: &[(i64, i64)], time: i64) -> Vec<(MoveState, i64)> {
+    let inside = |intervals: &[(i64, i64)], t: i64| intervals.iter().any(|&(l, r)| l <= t && t < r);
+    let mut points: Vec<i64> = stops.iter().chain(chills)
+        .flat_map(|&(l, r)| [l, r])
+        .filter(|&t| t > 1 && t <= time)
+        .chain([1, time.saturating_add(1)])
+        .collect();
+    points.sort_unstable();
+    points.dedup();
+    let mut runs: Vec<(MoveState, i64)> = Vec::new();
+    for lr in points.windows(2) {
+        let state =
+            if inside(stops, lr[0]) { MoveState::Stopped }
+            else if inside(chills, lr[0]) { MoveState::Chilled }
+            else { MoveState::Normal };
+        match runs.last_mut() {
+            Some(last) if last.0 == state => last.1 += lr[1] - lr[0],
+            _ => runs.push((state, lr[1] - lr[0])),
+        }
+    }
+    return runs;
+}
@@ -0,0 +1,108 @@
+use crate::common::*;
+use crate::parse_data;
+
+/// 问题的严重程度，有错误的数据无法读取
+#[derive(serde::Serialize, strum::Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[strum(serialize = "警告")]
+    Warning,
+    #[strum(serialize = "错误")]
+    Error,
+}
+
+/// 数据中的一个问题，`zombie` 为该行的僵尸名字，无法读取的行为行号
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct Issue {
+    pub zombie: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+// 场地的坐标范围，出生在场地外（如大波出生在 880 以右）是允许的
+const GRID: std::ops::RangeInclusive<i64> = 0..=879;
+
+/// 检查一只僵尸的数据，返回所有问题
+///
+/// 错误：出生范围或速度范围的左端大于右端，速度为负数，动画轨道不足两个坐标，
+/// `regular` 的两条轨道长度不同，`chill_immune` 但不 `freeze_immune`，`zomboni` 不免疫冰冻或减速；
+/// 警告：出生范围超出 0-879，动画轨道不单调（僵尸会后退，读报僵尸即是如此）。
+pub fn check_zombie_data(data: &ZombieData) -> Vec<(Severity, String)> {
+    let mut issues = Vec::new();
+    let mut warnings = Vec::new();
+    let mut error = |message: &str| issues.push((Severity::Error, message.to_string()));
+    for (spawn, what) in [(data.spawn, "出生范围"), (data.spawn_hugewave, "大波出生范围")] {
+        if spawn.0 > spawn.1 {
+            error(&format!("{what}的左端大于右端"));
+        } else if !GRID.contains(&spawn.0) || !GRID.contains(&spawn.1) {
+            warnings.push((Severity::Warning, format!("{what} {}-{} 超出 0-879", spawn.0, spawn.1)));
+        }
+    }
+    if data.speed.0 < Num::new(0, 1) {
+        error("速度为负数");
+    }
+    if data.speed.0 > data.speed.1 {
+        error("速度范围的左端大于右端");
+    }
+    if data.chill_immune && !data.freeze_immune {
+        error("免疫减速但不免疫冰冻");
+    }
+    let tracks: Vec<&Vec<Num>> = match &data.movement_type {
+        MovementType::Animation(x) | MovementType::Dancing(x) => vec![x],
+        MovementType::Regular(a, b) => {
+            if a.len() != b.len() {
+                error(&format!("regular 的两条动画轨道长度不同（{} 和 {}）", a.len() + 1, b.len() + 1));
+            }
+            vec![a, b]
+        },
+        MovementType::Zomboni => {
+            if !data.freeze_immune || !data.chill_immune {
+                error("zomboni 的移动方式不受减速影响，应当免疫冰冻和减速");
+            }
+            vec![]
+        },
+        MovementType::Constant | MovementType::DanceCheat => vec![],
+    };
+    if tracks.iter().any(|x| x.is_empty()) {
+        error("动画轨道至少需要两个坐标");
+    }
+    if !data.movement_type.is_monotone() && !matches!(data.movement_type, MovementType::DanceCheat) {
+        warnings.push((Severity::Warning, "动画轨道不单调，僵尸会后退".to_string()));
+    }
+    issues.append(&mut warnings);
+    return issues;
+}
+
+/// 检查与 `assets/data.csv` 格式相同的 CSV 内容，返回所有问题，不会因为某一行有误而中止
+pub fn validate(file_content: &[u8]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    for (i, row) in parse_data::read_rows(file_content).enumerate() {
+        let row = match row {
+            Ok(x) => x,
+            Err(e) => {
+                let line = e.position().map_or(i as u64 + 2, csv::Position::line);
+                issues.push(Issue { zombie: format!("第 {line} 行"), severity: Severity::Error, message: e.to_string() });
+                continue;
+            },
+        };
+        let mut push = |severity: Severity, message: String| {
+            issues.push(Issue { zombie: row.name.clone(), severity: severity, message: message });
+        };
+        if row.name.trim().is_empty() {
+            push(Severity::Error, "僵尸名字不能为空".to_string());
+            continue;
+        }
+        // 与读取数据时相同，别名视为同一种内置僵尸，名字不区分大小写
+        let key = ZombieName::new(&row.name).key();
+        if seen.contains(&key) {
+            push(Severity::Error, "出现了多次".to_string());
+        }
+        seen.push(key);
+        match parse_data::convert_zombie_data(&row) {
+            Ok(data) => check_zombie_data(&data).into_iter().for_each(|(severity, message)| push(severity, message)),
+            Err(e) => push(Severity::Error, e.to_string()),
+        }
+    }
+    return issues;
+}
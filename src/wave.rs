@@ -0,0 +1,56 @@
+use crate::common::*;
+use crate::explosion::Scene;
+use crate::zombie_db::ZombieDb;
+use crate::error::Error;
+use rayon::prelude::*;
+
+/// 僵尸能否在该场景出现，`early` 表示第 1-5 波（泳池只允许部分水路僵尸出现）
+pub fn can_spawn(data: &ZombieData, scene: Scene, early: bool) -> bool {
+    let (on_ground, in_pool) = if early {data.if_generate_in_wave1to5} else {data.if_generate_in};
+    return match scene {
+        Scene::Pool => on_ground || in_pool,
+        Scene::Yard | Scene::Roof => on_ground,
+    };
+}
+
+/// 一波中每只僵尸是各种类型的概率，按出怪权重计算，只包含概率不为 0 的类型
+///
+/// 每只僵尸视为独立地按权重从允许的类型中抽取，不考虑每波的点数上限。
+///
+/// # Errors
+///
+/// `zombies` 中有数据中没有的僵尸时返回 [`Error::UnknownZombie`]。
+pub fn composition(db: &ZombieDb, zombies: &[ZombieName], scene: Scene, hugewave: bool,
+                   early: bool) -> Result<Vec<(ZombieName, f64)>, Error> {
+    let mut weights: Vec<(ZombieName, u32)> = Vec::new();
+    for x in zombies {
+        let data = db.get(x)?;
+        let weight = if hugewave {data.summon_weight_hugewave} else {data.summon_weight_normal};
+        if can_spawn(data, scene, early) && weight > 0 {
+            weights.push((x.clone(), weight));
+        }
+    }
+    let total: u32 = weights.iter().map(|x| x.1).sum();
+    return Ok(weights.into_iter().map(|(x, w)| (x, f64::from(w) / f64::from(total))).collect());
+}
+
+/// 按 `composition` 随机抽取类型的一只僵尸的坐标分布，`scenario.zombie_type` 不使用
+///
+/// # Errors
+///
+/// 任一类型的计算出错时返回该错误，见 [`ZombieDb::calculate`]。
+pub fn mixture(db: &ZombieDb, composition: &[(ZombieName, f64)], scenario: &Scenario) -> Result<PosDistribution, Error> {
+    let dists: Vec<(PosDistribution, f64)> = composition.par_iter()
+        .map(|(x, p)| Ok((db.calculate(&Scenario { zombie_type: x.clone(), ..scenario.clone() })?, *p)))
+        .collect::<Result<_, Error>>()?;
+    let mut result = PosDistribution::empty();
+    for (d, p) in &dists {
+        for (i, y) in d.dist.iter().enumerate() {
+            result.add(d.offset + i as i64, p * y);
+        }
+        result.min = f64::min(result.min, d.min);
+        result.max = f64::max(result.max, d.max);
+    }
+    result.trim();
+    return Ok(result);
+}
@@ -0,0 +1,140 @@
+use crate::common::*;
+use crate::calculate_pos_distribution::{calculate_pos_distribution, calculate_series};
+use crate::joint::{self, PosEvent};
+use crate::kill::{self, Strike};
+use crate::parse_data;
+use crate::error::Error;
+use std::sync::LazyLock;
+
+/// 内置的僵尸数据文件 `assets/data.csv`
+pub const BUILTIN_DATA: &[u8] = include_bytes!("../assets/data.csv");
+
+static BUILTIN_DB: LazyLock<ZombieDb> = LazyLock::new(|| ZombieDb::from_csv(BUILTIN_DATA).expect("内置数据有误"));
+
+/// 僵尸数据库，按名字索引，包括数据文件中的自定义僵尸
+pub struct ZombieDb {
+    // 以 ZombieName::key 为键
+    zombies: HashMap<String, ZombieData>,
+    // 数据文件中的顺序
+    order: Vec<ZombieName>,
+}
+
+impl ZombieDb {
+    /// 内置的僵尸数据（`assets/data.csv`）
+    pub fn builtin() -> &'static ZombieDb {
+        return &BUILTIN_DB;
+    }
+
+    /// 从与 `assets/data.csv` 格式相同的 CSV 内容构建数据库
+    ///
+    /// # Errors
+    ///
+    /// 出生范围、速度等数据有误时返回错误，见 [`parse_data::get_zombie_db`]。
+    pub fn from_csv(file_content: &[u8]) -> Result<ZombieDb, Error> {
+        let zombies = parse_data::get_zombie_db(file_content)?;
+        let order = zombies.iter().map(|x| x.0.clone()).collect();
+        return Ok(ZombieDb { zombies: zombies.into_iter().map(|(name, data)| (name.key(), data)).collect(), order: order });
+    }
+
+    /// 从与 `assets/data.csv` 格式相同的 CSV 文件构建数据库
+    ///
+    /// # Errors
+    ///
+    /// 读取文件失败时返回 [`Error::Io`]，内容有误时同 [`ZombieDb::from_csv`]。
+    pub fn from_file(path: &std::path::Path) -> Result<ZombieDb, Error> {
+        let content = std::fs::read(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        return ZombieDb::from_csv(&content);
+    }
+
+    /// 数据中的所有僵尸，按数据文件中的顺序
+    pub fn names(&self) -> &[ZombieName] {
+        return &self.order;
+    }
+
+    /// 按名字查找数据中的僵尸，不区分大小写，内置僵尸类型可以用别名，返回数据文件中的写法（内置僵尸类型为标准写法）
+    ///
+    /// # Errors
+    ///
+    /// 数据中没有该僵尸时返回 [`Error::UnknownZombie`]。
+    pub fn resolve(&self, name: &str) -> Result<ZombieName, Error> {
+        let key = ZombieName::new(name).key();
+        return self.order.iter().find(|x| x.key() == key).cloned().ok_or_else(|| Error::UnknownZombie(name.trim().to_string()));
+    }
+
+    /// 僵尸的数据
+    ///
+    /// # Errors
+    ///
+    /// 数据中没有该僵尸时返回 [`Error::UnknownZombie`]。
+    pub fn get(&self, name: &ZombieName) -> Result<&ZombieData, Error> {
+        return self.zombies.get(&name.key()).ok_or_else(|| Error::UnknownZombie(name.to_string()));
+    }
+
+    /// 检查僵尸类型和目标时间后计算分布
+    ///
+    /// # Errors
+    ///
+    /// 数据中没有该僵尸时返回 [`Error::UnknownZombie`]，目标时间为负数时返回 [`Error::InvalidTime`]，
+    /// 精确计算溢出时返回 [`Error::Overflow`]。
+    pub fn calculate(&self, scenario: &Scenario) -> Result<PosDistribution, Error> {
+        let data = self.get(&scenario.zombie_type)?;
+        scenario.validate()?;
+        return calculate_pos_distribution(data, scenario);
+    }
+
+    /// 检查僵尸类型和各个目标时间后计算同一场景下多个目标时间的分布，见 [`calculate_series`]
+    ///
+    /// # Errors
+    ///
+    /// 同 [`ZombieDb::calculate`]，任一目标时间为负数时返回 [`Error::InvalidTime`]。
+    pub fn calculate_series(&self, scenario: &Scenario, times: &[i64]) -> Result<Vec<PosDistribution>, Error> {
+        let data = self.get(&scenario.zombie_type)?;
+        check_times(times.iter().copied())?;
+        return calculate_series(data, scenario, times);
+    }
+
+    /// 同一只僵尸同时满足所有条件的概率，见 [`joint::joint_prob`]
+    ///
+    /// # Errors
+    ///
+    /// 同 [`ZombieDb::calculate_series`]，某个条件的坐标范围左边界大于右边界时返回 [`Error::InvalidRange`]，
+    /// 其余见 [`joint::joint_prob`]。
+    pub fn joint_prob(&self, scenario: &Scenario, events: &[PosEvent]) -> Result<f64, Error> {
+        let data = self.get(&scenario.zombie_type)?;
+        check_times(events.iter().map(|x| x.time))?;
+        if let Some(event) = events.iter().find(|x| x.range.0 > x.range.1) {
+            return Err(Error::InvalidRange(event.range.0, event.range.1));
+        }
+        return joint::joint_prob(data, scenario, events);
+    }
+
+    /// 所有爆炸结束后僵尸受到的总伤害的分布，见 [`kill::damage_distribution`]
+    ///
+    /// # Errors
+    ///
+    /// 同 [`ZombieDb::calculate_series`]，其余见 [`kill::damage_distribution`]。
+    pub fn damage_distribution(&self, scenario: &Scenario, strikes: &[Strike]) -> Result<Vec<(i64, f64)>, Error> {
+        let data = self.get(&scenario.zombie_type)?;
+        check_times(strikes.iter().map(|x| x.time))?;
+        return kill::damage_distribution(data, scenario, strikes);
+    }
+
+    /// 所有爆炸结束后僵尸死亡的概率，见 [`kill::kill_prob`]
+    ///
+    /// # Errors
+    ///
+    /// 同 [`ZombieDb::damage_distribution`]。
+    pub fn kill_prob(&self, scenario: &Scenario, strikes: &[Strike]) -> Result<f64, Error> {
+        let data = self.get(&scenario.zombie_type)?;
+        check_times(strikes.iter().map(|x| x.time))?;
+        return kill::kill_prob(data, scenario, strikes);
+    }
+}
+
+// 各个时间都不能为负数
+fn check_times(mut times: impl Iterator<Item = i64>) -> Result<(), Error> {
+    return match times.find(|&t| t < 0) {
+        Some(time) => Err(Error::InvalidTime(time)),
+        None => Ok(()),
+    };
+}
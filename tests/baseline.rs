@@ -0,0 +1,33 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod common;
+
+use common::assert_dist_eq;
+use std::str::FromStr;
+use zombie_movement_calculator::{PosDistribution, Scenario, ZombieDb, ZombieType};
+
+// 最初版本（只支持一次冰）对每种僵尸不用冰和用一次冰的计算结果，列为 zombie,ice,time,min,max,first,dist，
+// dist 为从坐标 first 开始、空格分隔的概率，ice 为 0 表示不用冰
+const BASELINE: &str = include_str!("baseline.csv");
+
+#[test]
+fn single_ice_matches_baseline() {
+    let db = ZombieDb::builtin();
+    for line in BASELINE.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let ice: i64 = fields[1].parse().unwrap();
+        let time: i64 = fields[2].parse().unwrap();
+        let expected = PosDistribution {
+            offset: fields[5].parse().unwrap(),
+            dist: fields[6].split(' ').map(|x| x.parse().unwrap()).collect(),
+            min: fields[3].parse().unwrap(),
+            max: fields[4].parse().unwrap(),
+        };
+        let scenario = Scenario {
+            ice_times: if ice > 0 {vec![ice]} else {Vec::new()},
+            ..Scenario::new(ZombieType::from_str(fields[0]).unwrap(), time)
+        };
+        let d = db.calculate(&scenario).unwrap();
+        assert_dist_eq(&expected, &d, 1e-12);
+    }
+}
@@ -0,0 +1,66 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::batch::{read_batch_csv, read_batch_json, run_batch, write_batch_csv};
+use zombie_movement_calculator::{Error, Scenario, ZombieDb, ZombieType};
+
+const CSV: &str = "zombie,ice,time,hugewave,range_l,range_r,chill,ice_source,butter
+giga,,200,,0,817,,,
+pogo,100 2300,2600,true,,,,regular,
+catapult,,900,,700,,300:100:500,,200
+";
+
+const JSON: &str = r#"[
+    {"zombie": "giga", "time": 200, "range_l": 0, "range_r": 817},
+    {"zombie": "pogo", "ice": [100, 2300], "time": 2600, "hugewave": true, "ice_source": "regular"},
+    {"zombie": "catapult", "time": 900, "range_l": 700, "chill": "300:100:500", "butter": 200}
+]"#;
+
+#[test]
+fn csv_and_json_queries_agree() {
+    let db = ZombieDb::builtin();
+    let csv = run_batch(db, &read_batch_csv(CSV.as_bytes()).unwrap()).into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    let json = run_batch(db, &read_batch_json(JSON.as_bytes()).unwrap()).into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    let mut csv_out = Vec::new();
+    let mut json_out = Vec::new();
+    write_batch_csv(&mut csv_out, &csv).unwrap();
+    write_batch_csv(&mut json_out, &json).unwrap();
+    assert_eq!(csv_out, json_out);
+    assert_eq!(csv.len(), 3);
+    assert_eq!(csv[1].prob, None);
+}
+
+#[test]
+fn batch_results_match_single_queries() {
+    let db = ZombieDb::builtin();
+    let results = run_batch(db, &read_batch_csv(CSV.as_bytes()).unwrap()).into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    let giga = db.calculate(&Scenario::new(ZombieType::GigaGargantuar, 200)).unwrap();
+    assert_eq!((results[0].min, results[0].max, results[0].prob), (giga.min, giga.max, Some(giga.prob_range_rounded(0, 817, false))));
+    // 只有 range_l 时只查询一个坐标
+    let catapult = db.calculate(&Scenario {
+        chills: results[2].chill.0.clone(),
+        butter_times: vec![200],
+        ..Scenario::new(ZombieType::Catapult, 900)
+    }).unwrap();
+    assert_eq!(results[2].prob, Some(catapult.prob(700)));
+}
+
+#[test]
+fn near_one_range_prob_is_rounded() {
+    // 与直接查询相同，累加误差使概率略小于 1 时输出 1
+    let db = ZombieDb::builtin();
+    let d = db.calculate(&Scenario::new(ZombieType::Catapult, 300)).unwrap();
+    assert!(d.prob_range(-1000, 1000) < 1.0);
+    let queries = read_batch_csv("zombie,time,range_l,range_r\ncatapult,300,-1000,1000\n".as_bytes()).unwrap();
+    assert_eq!(run_batch(db, &queries)[0].as_ref().unwrap().prob, Some(1.0));
+}
+
+#[test]
+fn failing_queries_do_not_stop_the_batch() {
+    let db = ZombieDb::builtin();
+    let queries = read_batch_csv("zombie,time\ngiga,-1\ngiga,200\nnobody,200\npogo,300\n".as_bytes()).unwrap();
+    let results = run_batch(db, &queries);
+    assert!(matches!(results[0], Err(Error::InvalidTime(-1))));
+    assert!(matches!(results[2], Err(Error::UnknownZombie(_))));
+    assert_eq!(results[1].as_ref().unwrap().time, 200);
+    assert_eq!(results[3].as_ref().unwrap().time, 300);
+}
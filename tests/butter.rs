@@ -0,0 +1,40 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod common;
+
+use common::assert_dist_eq;
+use zombie_movement_calculator::{Error, PosEvent, Scenario, ZombieDb, ZombieType};
+
+#[test]
+fn butter_stops_without_chilling() {
+    let db = ZombieDb::builtin();
+    // 黄油期间不移动，之后以原速移动，与少移动这段时间的结果相同
+    for zombie in [ZombieType::Catapult, ZombieType::Football] {
+        let buttered = Scenario { butter_times: vec![100], ..Scenario::new(zombie, 900) };
+        let shorter = Scenario::new(zombie, 501);
+        assert_dist_eq(&db.calculate(&buttered).unwrap(), &db.calculate(&shorter).unwrap(), 0.0);
+    }
+    // 目标时间之后和不大于 0 的黄油被忽略
+    let ignored = Scenario { butter_times: vec![0, 901], ..Scenario::new(ZombieType::Catapult, 900) };
+    assert_dist_eq(&db.calculate(&ignored).unwrap(), &db.calculate(&Scenario::new(ZombieType::Catapult, 900)).unwrap(), 0.0);
+}
+
+#[test]
+fn butter_pauses_dancing_walk() {
+    let db = ZombieDb::builtin();
+    // 进场中被黄油暂停 399cs，之后继续进场
+    let buttered = Scenario { butter_times: vec![100], ..Scenario::new(ZombieType::Dancing, 900) };
+    let d = db.calculate(&buttered).unwrap();
+    assert_dist_eq(&d, &db.calculate(&Scenario::new(ZombieType::Dancing, 501)).unwrap(), 0.0);
+    assert!(d.max < db.calculate(&Scenario::new(ZombieType::Dancing, 99)).unwrap().min);
+    let range = (d.quantile(0.2), d.quantile(0.7));
+    let joint = db.joint_prob(&buttered, &[PosEvent { time: 900, range: range }]).unwrap();
+    assert!((joint - d.prob_range(range.0, range.1)).abs() < 1e-9);
+}
+
+#[test]
+fn butter_near_max_time_does_not_overflow() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario { butter_times: vec![i64::MAX - 100], ..Scenario::new(ZombieType::Pogo, i64::MAX) };
+    assert!(matches!(db.calculate(&scenario), Err(Error::Overflow)));
+}
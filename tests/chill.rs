@@ -0,0 +1,70 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod common;
+
+use common::assert_dist_eq;
+use std::num::NonZeroU32;
+use zombie_movement_calculator::{Chill, Error, PosEvent, Scenario, ZombieDb, ZombieType};
+
+#[test]
+fn windows_merge_only_touching_hits() {
+    let chill = Chill { start: 100, interval: NonZeroU32::new(3000), end: None };
+    assert_eq!(chill.windows(3500), vec![(100, 1099), (3100, 4099)]);
+    let chill = Chill { start: 100, interval: NonZeroU32::new(999), end: Some(2100) };
+    assert_eq!(chill.windows(3500), vec![(100, 3097)]);
+    assert_eq!(Chill::once(100).windows(50), Vec::new());
+    assert_eq!(Chill::once(i64::MAX - 10).windows(i64::MAX), vec![(i64::MAX - 10, i64::MAX)]);
+    let chill = Chill { start: i64::MAX - 1500, interval: NonZeroU32::new(999), end: None };
+    assert_eq!(chill.windows(i64::MAX), vec![(i64::MAX - 1500, i64::MAX)]);
+}
+
+#[test]
+fn periodic_chill_equals_explicit_hits() {
+    let db = ZombieDb::builtin();
+    for zombie in [ZombieType::Pogo, ZombieType::Catapult, ZombieType::DCFast] {
+        let periodic = Scenario { chills: vec![Chill { start: 100, interval: NonZeroU32::new(3000), end: None }],
+                                  ..Scenario::new(zombie, 3500) };
+        let explicit = Scenario { chills: vec![Chill::once(100), Chill::once(3100)], ..Scenario::new(zombie, 3500) };
+        assert_dist_eq(&db.calculate(&periodic).unwrap(), &db.calculate(&explicit).unwrap(), 0.0);
+    }
+    // 动画僵尸：两次命中间隔 1100cs，中间有 101cs 原速
+    let periodic = Scenario { chills: vec![Chill { start: 100, interval: NonZeroU32::new(1100), end: None }],
+                              ..Scenario::new(ZombieType::Football, 1400) };
+    let explicit = Scenario { chills: vec![Chill::once(100), Chill::once(1200)], ..Scenario::new(ZombieType::Football, 1400) };
+    assert_dist_eq(&db.calculate(&periodic).unwrap(), &db.calculate(&explicit).unwrap(), 0.0);
+    let periodic = db.calculate(&Scenario { chills: vec![Chill { start: 100, interval: NonZeroU32::new(3000), end: None }],
+                                            ..Scenario::new(ZombieType::Pogo, 3500) }).unwrap();
+    assert!((periodic.min - -417.016).abs() < 1e-3);
+}
+
+#[test]
+fn non_positive_interval_is_rejected() {
+    assert!(matches!("100:0".parse::<Chill>(), Err(Error::InvalidTime(0))));
+    assert!(matches!("100:-5:2000".parse::<Chill>(), Err(Error::InvalidTime(-5))));
+    assert!(matches!("100:x".parse::<Chill>(), Err(Error::InvalidNumber(_))));
+    assert!(matches!("2000:150:1000".parse::<Chill>(), Err(Error::InvalidWindow(2000, 1000))));
+    assert_eq!("1000:150:1000".parse::<Chill>().unwrap().windows(3000), vec![(1000, 1999)]);
+    assert_eq!(Chill { start: 2000, interval: NonZeroU32::new(150), end: Some(1000) }.windows(3000), Vec::new());
+    assert_eq!("100:999:2100".parse::<Chill>().unwrap(), Chill { start: 100, interval: NonZeroU32::new(999), end: Some(2100) });
+    assert_eq!("100".parse::<Chill>().unwrap().to_string(), "100");
+}
+
+#[test]
+fn dancing_keeps_walking_when_chilled() {
+    let db = ZombieDb::builtin();
+    let chilled = Scenario { chills: vec![Chill::once(100)], ..Scenario::new(ZombieType::Dancing, 1000) };
+    let d = db.calculate(&chilled).unwrap();
+    // 减速后继续进场，比在 100cs 停下走得远，比不减速走得近
+    let stopped = db.calculate(&Scenario::new(ZombieType::Dancing, 99)).unwrap();
+    let normal = db.calculate(&Scenario::new(ZombieType::Dancing, 1000)).unwrap();
+    assert!(d.max < stopped.min, "{} {}", d.max, stopped.min);
+    assert!(d.min > normal.max, "{} {}", d.min, normal.max);
+    // 被冰后不再移动：减速后 200cs 被冰与只进场到 200cs 相同
+    let iced = db.calculate(&Scenario { ice_times: vec![201], ..chilled.clone() }).unwrap();
+    let until_ice = db.calculate(&Scenario { chills: vec![Chill::once(100)], ..Scenario::new(ZombieType::Dancing, 200) }).unwrap();
+    assert_dist_eq(&iced, &until_ice, 0.0);
+    // 联合查询与单独计算一致
+    let range = (d.quantile(0.2), d.quantile(0.7));
+    let joint = db.joint_prob(&chilled, &[PosEvent { time: 1000, range: range }]).unwrap();
+    assert!((joint - d.prob_range(range.0, range.1)).abs() < 1e-9);
+}
@@ -0,0 +1,246 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::process::{Command, Output, Stdio};
+use zombie_movement_calculator::{Scenario, ZombieDb, ZombieType};
+
+fn run(args: &[&str]) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_zombie_movement_calculator")).args(args).output().unwrap();
+}
+
+// 命令失败并给出错误信息，而不是 panic
+fn assert_rejected(args: &[&str]) {
+    let output = run(args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{args:?} 应当失败");
+    assert!(!stderr.contains("panicked"), "{args:?} panic 了: {stderr}");
+}
+
+#[test]
+fn zero_counts_are_rejected() {
+    assert_rejected(&["group", "-z", "giga:0", "-t", "500"]);
+    assert_rejected(&["group", "-z", "giga:0", "-t", "500", "-f", "csv"]);
+    assert_rejected(&["wave", "-n", "0", "-t", "300"]);
+}
+
+#[test]
+fn wave_runs_with_one_zombie() {
+    let output = run(&["wave", "-n", "1", "-t", "300", "-s", "yard"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("最左"));
+}
+
+#[test]
+fn wave_of_dancecheat_matches_group() {
+    // 舞王秘籍僵尸的分布是近似计算的，wave 与 group 输出分布时的容差相同
+    let data: Vec<String> = include_str!("../assets/data.csv").lines().map(|x| {
+        if x.starts_with("DCFast,") {
+            x.replacen("270,0,0,", "270,100,100,", 1)
+        } else {
+            x.to_string()
+        }
+    }).collect();
+    let path = std::env::temp_dir().join(format!("zmc-dc-wave-{}.csv", std::process::id()));
+    std::fs::write(&path, data.join("\n")).unwrap();
+    let wave = run(&["wave", "--data", path.to_str().unwrap(), "-z", "dcfast", "-n", "1", "-t", "1000"]);
+    let group = run(&["group", "--data", path.to_str().unwrap(), "-z", "dcfast:1", "-t", "1000"]);
+    std::fs::remove_file(&path).unwrap();
+    let wave = String::from_utf8_lossy(&wave.stdout).into_owned();
+    let group = String::from_utf8_lossy(&group.stdout).into_owned();
+    assert_eq!(wave.lines().nth(1), group.lines().next());
+}
+
+#[test]
+fn data_file_replaces_builtin_data() {
+    let data = include_str!("../assets/data.csv").replacen("constant,,0.45,0.45", "constant,,0.9,0.9", 1);
+    let path = std::env::temp_dir().join(format!("zmc-fast-pogo-{}.csv", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    let output = run(&["--data", path.to_str().unwrap(), "-z", "pogo", "-t", "500", "-r", "0", "400"]);
+    let builtin = run(&["-z", "pogo", "-t", "500", "-r", "0", "400"]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0-400: 1\n");
+    assert_eq!(String::from_utf8_lossy(&builtin.stdout), "0-400: 0\n");
+}
+
+#[test]
+fn data_file_adds_custom_zombies() {
+    let data = include_str!("../assets/data.csv");
+    let pogo = data.lines().find(|x| x.starts_with("Pogo,")).unwrap().replacen("Pogo,", "SlowPogo,", 1);
+    let path = std::env::temp_dir().join(format!("zmc-custom-{}.csv", std::process::id()));
+    std::fs::write(&path, format!("{data}{pogo}\n")).unwrap();
+    let output = run(&["--data", path.to_str().unwrap(), "-z", "slowpogo", "-t", "500", "-r", "0", "600"]);
+    let table = run(&["table", "--data", path.to_str().unwrap(), "--window", "100", "100", "-f", "csv"]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0-600: 1\n");
+    assert!(String::from_utf8_lossy(&table.stdout).contains("SlowPogo,100,"));
+    assert_rejected(&["-z", "slowpogo", "-t", "500"]);
+}
+
+#[test]
+fn bad_data_file_is_rejected() {
+    let data = include_str!("../assets/data.csv").replacen("constant,,0.45,0.45", "constant,,0.45,0.23", 1);
+    let path = std::env::temp_dir().join(format!("zmc-bad-speed-{}.csv", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    assert_rejected(&["--data", path.to_str().unwrap(), "-z", "pogo", "-t", "500"]);
+    std::fs::remove_file(&path).unwrap();
+    assert_rejected(&["hit", "--data", "no-such-file.csv", "-z", "giga", "-t", "500", "-p", "cob", "-c", "7"]);
+}
+
+#[test]
+fn table_of_empty_data_is_rejected() {
+    let header = include_str!("../assets/data.csv").lines().next().unwrap();
+    let path = std::env::temp_dir().join(format!("zmc-empty-{}.csv", std::process::id()));
+    std::fs::write(&path, format!("{header}\n")).unwrap();
+    let output = run(&["table", "--data", path.to_str().unwrap(), "--window", "100", "100"]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("数据中没有任何僵尸"));
+}
+
+#[test]
+fn closed_stdout_is_a_write_error() {
+    // 计算完成前就关闭读端，写入分布 CSV 时管道已断开
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zombie_movement_calculator"))
+        .args(["-z", "giga", "-t", "3000", "-f", "csv"])
+        .stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("无法写入结果") && !stderr.contains("panicked"), "{stderr}");
+}
+
+#[test]
+fn validate_runs_without_loading_bad_data() {
+    let data = include_str!("../assets/data.csv").replacen("constant,,0.45,0.45", "constant,,0.45,0.23", 1);
+    let path = std::env::temp_dir().join(format!("zmc-validate-{}.csv", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    let output = run(&["validate", "--data", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.contains("Pogo") && stdout.contains("左端大于右端"), "{stdout}");
+    assert!(run(&["validate"]).status.success());
+}
+
+#[test]
+fn unsupported_queries_are_rejected() {
+    assert_rejected(&["joint", "-z", "dcfast", "--at", "300:0:600"]);
+    assert_rejected(&["kill", "-z", "dcfast", "--hit", "300:cob:8"]);
+    assert_rejected(&["joint", "-z", "giga"]);
+    assert_rejected(&["reach", "-z", "giga", "-x", "400", "--window", "-5", "100"]);
+    assert_rejected(&["group", "-z", "gigaa:2", "-t", "500"]);
+    assert_rejected(&["table", "-z", "giga", "-z", "pgo", "--window", "100", "100"]);
+}
+
+#[test]
+fn query_options_need_zombie_and_time() {
+    assert_rejected(&["-i", "100"]);
+    assert_rejected(&["--chill", "100", "-w"]);
+    assert_rejected(&["-z", "giga", "-i", "100"]);
+}
+
+#[test]
+fn usage_errors_are_reported_by_argument_parsing() {
+    // clap 在参数有误时返回状态码 2
+    let usage_error = |args: &[&str]| {
+        let output = run(args);
+        assert_eq!(output.status.code(), Some(2), "{args:?}: {}", String::from_utf8_lossy(&output.stderr));
+    };
+    usage_error(&["joint", "-z", "giga"]);
+    usage_error(&["group", "-z", "giga:0", "-t", "500"]);
+    usage_error(&["hit", "-z", "giga", "-p", "cob", "-c", "7"]);
+    usage_error(&["reach", "-x", "400"]);
+}
+
+// 最初版本交互模式下各僵尸冰时机 150、目标时间 800 的输出，每行为僵尸类型和输出，以制表符分隔
+const BASELINE_OUTPUT: &str = include_str!("baseline_cli.txt");
+
+#[test]
+fn single_ice_output_matches_baseline() {
+    for line in BASELINE_OUTPUT.lines() {
+        let (zombie, expected) = line.split_once('\t').unwrap();
+        let output = run(&["-z", zombie, "-i", "150", "-t", "800"]);
+        assert!(output.status.success(), "{zombie}");
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), expected, "{zombie}");
+    }
+}
+
+#[test]
+fn range_query_prints_probability() {
+    let output = run(&["-z", "giga", "-t", "500", "-r", "0", "817"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0-817: 1\n");
+    assert_rejected(&["-z", "giga", "-t", "500", "-r", "817", "0"]);
+    assert_rejected(&["-z", "giga", "-t", "-1"]);
+}
+
+#[test]
+fn json_output_matches_library() {
+    let output = run(&["-z", "giga", "-t", "200", "-r", "0", "817", "-f", "json"]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let d = ZombieDb::builtin().calculate(&Scenario::new(ZombieType::GigaGargantuar, 200)).unwrap();
+    assert_eq!(report["scenario"]["time"], 200);
+    assert_eq!(report["range"], serde_json::json!([0, 817]));
+    // serde_json 读取浮点数时可能有 1ulp 的误差
+    let close = |x: &serde_json::Value, y: f64| (x.as_f64().unwrap() - y).abs() < 1e-12;
+    assert!(close(&report["prob"], d.prob_range(0, 817)));
+    assert_eq!(report["distribution"]["offset"], d.offset);
+    assert!(close(&report["distribution"]["min"], d.min) && close(&report["distribution"]["max"], d.max));
+    let dist = report["distribution"]["dist"].as_array().unwrap();
+    assert_eq!(dist.len(), d.dist.len());
+    assert!(dist.iter().zip(&d.dist).all(|(x, &y)| close(x, y)));
+}
+
+#[test]
+fn batch_range_without_left_bound_is_rejected() {
+    let path = std::env::temp_dir().join(format!("zmc-batch-range-{}.csv", std::process::id()));
+    std::fs::write(&path, "zombie,time,range_l,range_r\ngiga,200,,817\n").unwrap();
+    let output = run(&["batch", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("缺少左边界"));
+}
+
+#[test]
+fn batch_reports_each_failing_query_and_keeps_going() {
+    let path = std::env::temp_dir().join(format!("zmc-batch-rows-{}.csv", std::process::id()));
+    std::fs::write(&path, "zombie,time\ngiga,-1\ngiga,200\nnobody,200\n").unwrap();
+    let output = run(&["batch", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("第 1 个查询有误") && stderr.contains("第 3 个查询有误"), "{stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 2, "{stdout}");
+    assert!(stdout.lines().nth(1).unwrap().starts_with("GigaGargantuar,,200,"), "{stdout}");
+}
+
+#[test]
+fn invalid_arguments_report_typed_errors() {
+    let stderr = |args: &[&str]| {
+        let output = run(args);
+        assert!(!output.status.success(), "{args:?} 应当失败");
+        return String::from_utf8_lossy(&output.stderr).into_owned();
+    };
+    assert!(stderr(&["reach", "-z", "giga", "-x", "400", "--window", "500", "100"]).contains("无效的时间范围 500-100"));
+    assert!(stderr(&["table", "-z", "giga", "--window", "100", "200", "--step", "0"]).contains("无效的时间间隔 0"));
+    assert!(stderr(&["table", "-z", "giga", "--window", "100", "200", "-q", "1.5"]).contains("无效的概率 1.5"));
+    assert!(stderr(&["hit", "-z", "giga", "-t", "200", "-p", "cob", "-c", "9", "-s", "yard", "-R", "6"]).contains("没有第 6 行"));
+    assert!(stderr(&["batch", "/nonexistent/queries.csv"]).contains("无法读取 /nonexistent/queries.csv"));
+    assert!(stderr(&["table", "-z", "giga", "--window", "100", "200", "-o", "/nonexistent/table.csv"]).contains("无法写入 /nonexistent/table.csv"));
+}
+
+#[test]
+fn explosion_position_outside_field_is_rejected() {
+    for args in [&["-c", "1e300"][..], &["-c", "NaN"], &["-c", "-1"], &["-x", "9223372036854775807"], &["-x", "-1000"]] {
+        let output = run(&[&["hit", "-z", "giga", "-t", "200", "-p", "cob"][..], args].concat());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{args:?}");
+        assert!(stderr.contains("无效的列数") || stderr.contains("无效的爆心横坐标"), "{args:?}: {stderr}");
+    }
+    assert_rejected(&["kill", "-z", "giga", "--hit", "200:cob:inf"]);
+    let output = run(&["hit", "-z", "giga", "-t", "200", "-p", "cob", "-x", "-35"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,14 @@
+#![allow(dead_code)]
+
+use zombie_movement_calculator::PosDistribution;
+
+// 两个分布的支撑集、极值相同，各坐标的概率之差不超过 tol
+pub fn assert_dist_eq(a: &PosDistribution, b: &PosDistribution, tol: f64) {
+    assert_eq!(a.support(0.0), b.support(0.0), "支撑集不同");
+    assert!((a.min - b.min).abs() <= 1e-9 && (a.max - b.max).abs() <= 1e-9,
+            "极值不同: {}-{} 和 {}-{}", a.min, a.max, b.min, b.max);
+    let (first, last) = a.support(0.0).expect("分布为空");
+    for x in first..=last {
+        assert!((a.prob(x) - b.prob(x)).abs() <= tol, "坐标 {x} 的概率不同: {} 和 {}", a.prob(x), b.prob(x));
+    }
+}
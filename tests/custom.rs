@@ -0,0 +1,65 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod common;
+
+use common::assert_dist_eq;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+use zombie_movement_calculator::{batch, Error, Scenario, ZombieDb, ZombieName, ZombieType};
+
+// 内置数据加上一只速度翻倍的撑杆和一只与 Pogo 相同的 SlowPogo
+fn custom_db() -> ZombieDb {
+    let data = include_str!("../assets/data.csv");
+    let pogo = data.lines().find(|x| x.starts_with("Pogo,")).unwrap();
+    let fast = pogo.replacen("Pogo,", "FastPogo,", 1).replacen("constant,,0.45,0.45", "constant,,0.9,0.9", 1);
+    let slow = pogo.replacen("Pogo,", "SlowPogo,", 1);
+    return ZombieDb::from_csv(format!("{data}{fast}\n{slow}\n").as_bytes()).unwrap();
+}
+
+#[test]
+fn names_resolve_against_loaded_data() {
+    let db = custom_db();
+    assert_eq!(ZombieType::from_str("giga").unwrap(), ZombieType::GigaGargantuar);
+    assert_eq!(db.resolve("giga").unwrap(), ZombieType::GigaGargantuar.into());
+    assert_eq!(db.resolve("FastPogo").unwrap(), db.resolve("fastpogo").unwrap());
+    assert_eq!(db.resolve("FastPogo").unwrap().builtin(), None);
+    assert_eq!(db.resolve("fastpogo").unwrap().to_string(), "FastPogo");
+    // 只解析内置僵尸类型，拼写错误和其他数据中的名字都不是僵尸类型
+    assert!(ZombieType::from_str("FastPogo").is_err());
+    assert!(matches!(db.resolve("FastPgo"), Err(Error::UnknownZombie(_))));
+    assert!(matches!(ZombieDb::builtin().resolve("FastPogo"), Err(Error::UnknownZombie(_))));
+    // 内置数据恰好包含所有内置僵尸类型
+    let builtin: Vec<ZombieName> = ZombieType::iter().map(ZombieName::from).collect();
+    assert_eq!(ZombieDb::builtin().names(), &builtin[..]);
+}
+
+#[test]
+fn custom_zombies_are_queryable() {
+    let db = custom_db();
+    let slow = db.resolve("slowpogo").unwrap();
+    let fast = db.resolve("FASTPOGO").unwrap();
+    assert_eq!(&db.names()[db.names().len() - 2..], &[fast.clone(), slow.clone()]);
+    let d = db.calculate(&Scenario { ice_times: vec![100], ..Scenario::new(slow.clone(), 600) }).unwrap();
+    let pogo = db.calculate(&Scenario { ice_times: vec![100], ..Scenario::new(ZombieType::Pogo, 600) }).unwrap();
+    assert_dist_eq(&d, &pogo, 1e-12);
+    // 名字不区分大小写
+    let fast = db.calculate(&Scenario::new("fastPOGO", 500)).unwrap();
+    assert!((fast.prob_range(0, 400) - 1.0).abs() < 1e-9);
+    assert!(matches!(ZombieDb::builtin().get(&slow), Err(Error::UnknownZombie(_))));
+}
+
+#[test]
+fn batch_queries_accept_custom_zombies() {
+    let db = custom_db();
+    let queries = batch::read_batch_csv("zombie,time,range_l,range_r\nFastPogo,500,0,400\n".as_bytes()).unwrap();
+    let result = batch::run_query(&db, &queries[0]).unwrap();
+    assert_eq!(result.zombie.to_string(), "FastPogo");
+    assert!((result.prob.unwrap() - 1.0).abs() < 1e-9);
+    // 结果可以读回
+    let mut written = Vec::new();
+    batch::write_batch_csv(&mut written, &[result]).unwrap();
+    let read: Vec<batch::BatchResult> = csv::Reader::from_reader(&written[..]).deserialize().collect::<Result<_, _>>().unwrap();
+    assert_eq!(read[0].zombie, db.resolve("fastpogo").unwrap());
+    let queries = batch::read_batch_csv("zombie,time\nFastPgo,500\n".as_bytes()).unwrap();
+    assert!(matches!(batch::run_query(&db, &queries[0]), Err(Error::UnknownZombie(_))));
+}
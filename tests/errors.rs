@@ -0,0 +1,94 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::{batch, joint, kill, search, table, wave, Error, Explosion, PosEvent, Scenario, Scene, Strike, ZombieDb, ZombieType};
+
+// 内置数据去掉 GigaGargantuar 一行
+fn db_without_giga() -> ZombieDb {
+    let data: Vec<&str> = include_str!("../assets/data.csv").lines().filter(|x| !x.starts_with("GigaGargantuar,")).collect();
+    return ZombieDb::from_csv(data.join("\n").as_bytes()).unwrap();
+}
+
+#[test]
+fn unknown_zombie_is_an_error() {
+    let db = &db_without_giga();
+    let unknown = ZombieType::GigaGargantuar;
+    assert!(matches!(db.get(&unknown.into()), Err(Error::UnknownZombie(_))));
+    assert!(matches!(db.calculate(&Scenario::new(unknown, 100)), Err(Error::UnknownZombie(_))));
+    assert!(matches!(db.calculate_series(&Scenario::new(unknown, 100), &[100]), Err(Error::UnknownZombie(_))));
+    assert!(matches!(wave::composition(db, &[ZombieType::Regular.into(), unknown.into()], Scene::Yard, false, false),
+                     Err(Error::UnknownZombie(_))));
+    let scenario = Scenario::new(ZombieType::Regular, 100);
+    assert!(matches!(table::coordinate_table(db, &[unknown.into()], &scenario, (100, 200), 100, &[0.5]),
+                     Err(Error::UnknownZombie(_))));
+}
+
+#[test]
+fn negative_times_are_errors() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario::new(ZombieType::Regular, 100);
+    assert!(matches!(db.calculate(&Scenario::new(ZombieType::Regular, -1)), Err(Error::InvalidTime(-1))));
+    assert!(matches!(db.calculate_series(&scenario, &[100, -2]), Err(Error::InvalidTime(-2))));
+    assert!(matches!(db.joint_prob(&scenario, &[PosEvent { time: -3, range: (0, 800) }]), Err(Error::InvalidTime(-3))));
+    assert!(matches!(db.kill_prob(&scenario, &[Strike::new(-4, Explosion::Cob, 600)]), Err(Error::InvalidTime(-4))));
+    assert!(matches!(search::reach_times(db, &scenario, 400, 0.5, (-5, 100)), Err(Error::InvalidTime(-5))));
+    assert!(matches!(search::first_passage(db, &scenario, 400, (-6, 100)), Err(Error::InvalidTime(-6))));
+    assert!(matches!(search::hit_curve(db, &scenario, Explosion::Cob, 600, (-7, 100)), Err(Error::InvalidTime(-7))));
+}
+
+#[test]
+fn dancecheat_joint_queries_are_errors() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario::new(ZombieType::DCFast, 300);
+    assert!(matches!(db.joint_prob(&scenario, &[PosEvent { time: 300, range: (0, 600) }]), Err(Error::JointUnsupported(_))));
+    assert!(matches!(db.kill_prob(&scenario, &[Strike::new(300, Explosion::Cob, 600)]), Err(Error::JointUnsupported(_))));
+    assert!(matches!(db.damage_distribution(&scenario, &[Strike::new(300, Explosion::Cob, 600)]),
+                     Err(Error::JointUnsupported(_))));
+}
+
+#[test]
+fn too_many_joint_events_are_errors() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario::new(ZombieType::Regular, 300);
+    let events: Vec<PosEvent> = (0..21).map(|t| PosEvent { time: t * 10, range: (0, 900) }).collect();
+    assert!(matches!(db.joint_prob(&scenario, &events), Err(Error::TooManyEvents(21))));
+    let strikes: Vec<Strike> = (0..21).map(|t| Strike::new(t * 10, Explosion::Cob, 600)).collect();
+    assert!(matches!(db.kill_prob(&scenario, &strikes), Err(Error::TooManyEvents(21))));
+    assert!(matches!(db.damage_distribution(&scenario, &strikes), Err(Error::TooManyEvents(21))));
+    assert!(db.joint_prob(&scenario, &events[..joint::MAX_EVENTS]).is_ok());
+    // 直接调用库函数也不会 panic
+    let data = db.get(&ZombieType::Regular.into()).unwrap();
+    assert!(matches!(joint::hit_patterns(data, &scenario, &events), Err(Error::TooManyEvents(21))));
+    assert!(matches!(kill::kill_prob(data, &scenario, &strikes), Err(Error::TooManyEvents(21))));
+}
+
+#[test]
+fn valid_queries_succeed() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario::new(ZombieType::Regular, 300);
+    let event = PosEvent { time: 300, range: (0, 900) };
+    assert!((db.joint_prob(&scenario, &[event]).unwrap() - 1.0).abs() < 1e-9);
+    assert!(db.kill_prob(&scenario, &[Strike::new(300, Explosion::Cob, 600)]).is_ok());
+    assert_eq!(search::reach_times(db, &scenario, 0, 0.5, (10, 5)).unwrap().first, None);
+}
+
+#[test]
+fn batch_range_needs_left_bound() {
+    let db = ZombieDb::builtin();
+    let queries = batch::read_batch_csv("zombie,time,range_l,range_r\ngiga,200,,817\ngiga,200,817,0\ngiga,200,0,\n".as_bytes()).unwrap();
+    assert!(matches!(batch::run_query(db, &queries[0]), Err(Error::MissingRangeLeft(817))));
+    assert!(matches!(batch::run_query(db, &queries[1]), Err(Error::InvalidRange(817, 0))));
+    assert!(batch::run_query(db, &queries[2]).unwrap().prob.is_some());
+}
+
+#[test]
+fn bad_data_is_rejected() {
+    let data = include_str!("../assets/data.csv");
+    let swapped = data.replacen("Pogo,780,819", "Pogo,819,780", 1);
+    assert!(matches!(ZombieDb::from_csv(swapped.as_bytes()), Err(Error::BadData { .. })));
+    let slow = data.replacen("constant,,0.45,0.45", "constant,,-0.45,0.45", 1);
+    assert!(matches!(ZombieDb::from_csv(slow.as_bytes()), Err(Error::BadData { .. })));
+    let pogo = data.lines().find(|x| x.starts_with("Pogo,")).unwrap();
+    let duplicated = format!("{data}{pogo}\n");
+    assert!(matches!(ZombieDb::from_csv(duplicated.as_bytes()), Err(Error::DuplicateZombie(_))));
+    assert!(matches!(ZombieDb::from_file(std::path::Path::new("no-such-file.csv")), Err(Error::Io(..))));
+}
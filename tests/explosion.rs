@@ -0,0 +1,58 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::{Explosion, Scenario, Scene, ZombieDb, ZombieType};
+
+#[test]
+fn hit_prob_sums_hit_range() {
+    let db = ZombieDb::builtin();
+    let data = db.get(&ZombieType::GigaGargantuar.into()).unwrap();
+    let d = db.calculate(&Scenario::new(ZombieType::GigaGargantuar, 800)).unwrap();
+    // 9 列炮爆心为 685
+    assert_eq!(Explosion::Cob.center_x(9.0), 685);
+    for explosion in [Explosion::Cob, Explosion::Cherry, Explosion::Doom] {
+        let center_x = explosion.center_x(7.5);
+        let (l, r) = explosion.hit_range(data, center_x).unwrap();
+        assert_eq!(explosion.hit_prob(data, &d, center_x), d.prob_range(l, r));
+        assert_eq!(explosion.hit_event(data, 800, center_x).range, (l, r));
+    }
+    // 半径更大的爆炸命中范围更大
+    let cob = Explosion::Cob.hit_range(data, 600).unwrap();
+    let doom = Explosion::Doom.hit_range(data, 600).unwrap();
+    assert!(doom.0 < cob.0 && cob.1 < doom.1);
+    assert_eq!(Explosion::Jalapeno.hit_range(data, 600), None);
+    assert_eq!(Explosion::Jalapeno.hit_prob(data, &d, 0), 1.0);
+}
+
+#[test]
+fn row_hit_probs_use_row_geometry() {
+    let db = ZombieDb::builtin();
+    let data = db.get(&ZombieType::GigaGargantuar.into()).unwrap();
+    let d = db.calculate(&Scenario::new(ZombieType::GigaGargantuar, 800)).unwrap();
+    let center_x = Explosion::Cob.center_x(8.0);
+    let rows = Explosion::Cob.row_hit_probs(data, &d, Scene::Yard, center_x, 3);
+    assert_eq!(rows.len(), 5);
+    assert!((rows[2] - Explosion::Cob.hit_prob(data, &d, center_x)).abs() < 1e-12);
+    // 上下相邻行只能命中防御框的一部分，防御框偏下，上一行的僵尸更难命中
+    assert!(rows[1] > 0.0 && rows[1] < rows[2] && rows[3] == rows[2]);
+    assert!(rows[0] == 0.0 && rows[4] == 0.0);
+    let rows = Explosion::Jalapeno.row_hit_probs(data, &d, Scene::Yard, 0, 3);
+    assert!((rows[2] - 1.0).abs() < 1e-12 && rows.iter().enumerate().all(|(i, &p)| i == 2 || p == 0.0));
+    assert_eq!(Explosion::Doom.row_hit_probs(data, &d, Scene::Pool, center_x, 3).len(), 6);
+}
+
+#[test]
+fn far_away_centers_do_not_overflow() {
+    let db = ZombieDb::builtin();
+    let data = db.get(&ZombieType::GigaGargantuar.into()).unwrap();
+    let d = db.calculate(&Scenario::new(ZombieType::GigaGargantuar, 800)).unwrap();
+    for center_x in [i64::MAX, i64::MIN, Explosion::Cob.center_x(1e300), Explosion::Cob.center_x(f64::NAN)] {
+        for explosion in [Explosion::Cob, Explosion::Doom] {
+            let (l, r) = explosion.hit_range(data, center_x).unwrap();
+            assert!(l <= r);
+            let event = explosion.hit_event(data, 800, center_x);
+            assert!(event.range.0 >= i64::MIN / 4 && event.range.1 <= i64::MAX / 4);
+            assert!(explosion.row_hit_probs(data, &d, Scene::Roof, center_x, 3).iter().all(|&p| (0.0..=1.0).contains(&p)));
+        }
+    }
+    assert_eq!(Explosion::Cob.hit_prob(data, &d, i64::MAX), 0.0);
+}
@@ -0,0 +1,60 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::group::{all_in_range, backmost, frontmost};
+use zombie_movement_calculator::{Scenario, ZombieDb, ZombieType};
+
+#[test]
+fn zero_count_gives_empty_distribution() {
+    let d = ZombieDb::builtin().calculate(&Scenario::new(ZombieType::Pogo, 500)).unwrap();
+    for result in [frontmost(&[(&d, 0)]), backmost(&[(&d, 0)]), frontmost(&[])] {
+        assert_eq!(result.support(0.0), None);
+        assert_eq!(result.prob_range(0, 879), 0.0);
+        assert!(result.slice(0, 879).is_empty());
+        assert!(serde_json::to_string(&result).unwrap().contains(r#""dist":[]"#));
+    }
+}
+
+#[test]
+fn single_zombie_group_is_the_zombie_itself() {
+    let d = ZombieDb::builtin().calculate(&Scenario::new(ZombieType::Pogo, 500)).unwrap();
+    let front = frontmost(&[(&d, 1)]);
+    assert_eq!(front.support(0.0), d.support(0.0));
+    let (first, last) = d.support(0.0).unwrap();
+    for x in first..=last {
+        assert!((front.prob(x) - d.prob(x)).abs() < 1e-12);
+    }
+    // 超出范围的部分被截掉
+    assert_eq!(d.slice(i64::MIN, i64::MAX), &d.dist[..]);
+    assert_eq!(d.slice(last + 1, last + 100), &[] as &[f64]);
+}
+
+#[test]
+fn group_of_independent_zombies() {
+    let db = ZombieDb::builtin();
+    let pogo = db.calculate(&Scenario::new(ZombieType::Pogo, 500)).unwrap();
+    let giga = db.calculate(&Scenario::new(ZombieType::GigaGargantuar, 500)).unwrap();
+    let group = [(&pogo, 2), (&giga, 1)];
+    let (front, back) = (frontmost(&group), backmost(&group));
+    // 最左一只不大于 x 等价于不是所有僵尸都大于 x，最右一只同理
+    for x in 600..=800 {
+        let front_cdf = 1.0 - pogo.prob_range(x + 1, 879).powi(2) * giga.prob_range(x + 1, 879);
+        assert!((front.prob_range(0, x) - front_cdf).abs() < 1e-9, "{x}");
+        let back_cdf = pogo.prob_range(0, x).powi(2) * giga.prob_range(0, x);
+        assert!((back.prob_range(0, x) - back_cdf).abs() < 1e-9, "{x}");
+    }
+    let in_range = pogo.prob_range(650, 800).powi(2) * giga.prob_range(650, 800);
+    assert!((all_in_range(&group, 650, 800) - in_range).abs() < 1e-12);
+}
+
+#[test]
+fn huge_counts_keep_probabilities_in_range() {
+    let db = ZombieDb::builtin();
+    let d = db.calculate(&Scenario::new(ZombieType::Regular, 1000)).unwrap();
+    for count in [i32::MAX as u32 + 1, u32::MAX] {
+        let group = [(&d, count)];
+        for p in [all_in_range(&group, 700, 800), frontmost(&group).prob_range(700, 800), backmost(&group).prob_range(700, 800)] {
+            assert!((0.0..=1.0).contains(&p), "{count}: {p}");
+        }
+        assert!(frontmost(&group).dist.iter().chain(&backmost(&group).dist).all(|p| (0.0..=1.0).contains(p)), "{count}");
+    }
+}
@@ -0,0 +1,20 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::{Scenario, ZombieDb, ZombieType};
+
+#[test]
+fn hugewave_uses_hugewave_spawn_range() {
+    let db = ZombieDb::builtin();
+    for zombie in [ZombieType::Regular, ZombieType::Football, ZombieType::Pogo] {
+        let data = db.get(&zombie.into()).unwrap();
+        let shift = data.spawn_hugewave.0 - data.spawn.0;
+        assert_eq!(data.spawn_hugewave.1 - data.spawn.1, shift);
+        let normal = db.calculate(&Scenario { ice_times: vec![200], ..Scenario::new(zombie, 600) }).unwrap();
+        let hugewave = db.calculate(&Scenario { ice_times: vec![200], hugewave: true, ..Scenario::new(zombie, 600) }).unwrap();
+        // 出生范围宽度相同时，分布整体平移
+        assert_eq!(hugewave.offset, normal.offset + shift);
+        assert_eq!(hugewave.dist, normal.dist);
+        assert!((hugewave.min - normal.min - shift as f64).abs() < 1e-9);
+        assert!((hugewave.max - normal.max - shift as f64).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,61 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod common;
+
+use common::assert_dist_eq;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+use zombie_movement_calculator::{IceSource, Scenario, ZombieDb, ZombieType};
+
+#[test]
+fn freeze_until_target_time_equals_earlier_target() {
+    let db = ZombieDb::builtin();
+    // 第二次冰冻结到目标时间之后，与只有第一次冰、目标时间为第二次冰前 1cs 的结果相同
+    let twice = Scenario { ice_times: vec![100, 2200], ..Scenario::new(ZombieType::Catapult, 2500) };
+    let once = Scenario { ice_times: vec![100], ..Scenario::new(ZombieType::Catapult, 2199) };
+    assert_dist_eq(&db.calculate(&twice).unwrap(), &db.calculate(&once).unwrap(), 0.0);
+    // 减速中被冰，冻结 300cs
+    let twice = Scenario { ice_times: vec![100, 1000], ..Scenario::new(ZombieType::Football, 1200) };
+    let once = Scenario { ice_times: vec![100], ..Scenario::new(ZombieType::Football, 999) };
+    assert_dist_eq(&db.calculate(&twice).unwrap(), &db.calculate(&once).unwrap(), 0.0);
+}
+
+#[test]
+fn last_ice_near_target_does_not_blow_up() {
+    // 三次冰都在减速结束后，不合并时有 201^3 种情况，最后一次冰冻结到目标时间之后
+    let db = ZombieDb::builtin();
+    let thrice = Scenario { ice_times: vec![100, 2200, 4300], ..Scenario::new(ZombieType::Catapult, 4600) };
+    let twice = Scenario { ice_times: vec![100, 2200], ..Scenario::new(ZombieType::Catapult, 4299) };
+    assert_dist_eq(&db.calculate(&thrice).unwrap(), &db.calculate(&twice).unwrap(), 0.0);
+}
+
+#[test]
+fn ice_sources_shift_effective_time() {
+    let db = ZombieDb::builtin();
+    for source in IceSource::iter() {
+        let planted = Scenario { ice_times: vec![100], ice_source: source, ..Scenario::new(ZombieType::Football, 1200) };
+        let effective = Scenario { ice_times: vec![100 + source.delay()], ..Scenario::new(ZombieType::Football, 1200) };
+        assert_dist_eq(&db.calculate(&planted).unwrap(), &db.calculate(&effective).unwrap(), 0.0);
+    }
+    assert_eq!(IceSource::from_str("imitator").unwrap().delay(), 420);
+    assert_eq!(IceSource::from_str("coffee").unwrap(), IceSource::Ice3);
+}
+
+#[test]
+fn frozen_dancecheat_stays_at_spawn() {
+    // 没有移动过时分布的方差为 0，位置即出生点
+    let db = ZombieDb::builtin();
+    for scenario in [Scenario::new(ZombieType::DCFast, 0), Scenario { ice_times: vec![1], ..Scenario::new(ZombieType::DCSlow, 300) }] {
+        let d = db.calculate(&scenario).unwrap();
+        assert!((d.prob_range(780, 819) - 1.0).abs() < 1e-12, "{scenario:?}");
+        assert!((d.prob_range(800, 800) - 1.0 / 40.0).abs() < 1e-12, "{scenario:?}");
+    }
+}
+
+#[test]
+fn huge_plant_time_never_takes_effect() {
+    let db = ZombieDb::builtin();
+    let planted = Scenario { ice_times: vec![i64::MAX - 50], ice_source: IceSource::Imitater, ..Scenario::new(ZombieType::Football, 1200) };
+    assert_eq!(planted.effective_ice_times(), vec![i64::MAX]);
+    assert_dist_eq(&db.calculate(&planted).unwrap(), &db.calculate(&Scenario::new(ZombieType::Football, 1200)).unwrap(), 0.0);
+}
@@ -0,0 +1,36 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::joint::hit_patterns;
+use zombie_movement_calculator::{PosEvent, Scenario, ZombieDb, ZombieType};
+
+#[test]
+fn single_event_equals_marginal() {
+    let db = ZombieDb::builtin();
+    for zombie in [ZombieType::Catapult, ZombieType::Football, ZombieType::Regular] {
+        let scenario = Scenario { ice_times: vec![200], ..Scenario::new(zombie, 700) };
+        let d = db.calculate(&scenario).unwrap();
+        let event = PosEvent { time: 700, range: (d.quantile(0.3), d.quantile(0.8)) };
+        let joint = db.joint_prob(&scenario, &[event]).unwrap();
+        assert!((joint - d.prob_range(event.range.0, event.range.1)).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn events_share_spawn_and_speed() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario::new(ZombieType::Catapult, 0);
+    let marginal = |event: PosEvent| {
+        let d = db.calculate(&Scenario { time: event.time, ..scenario.clone() }).unwrap();
+        d.prob_range(event.range.0, event.range.1)
+    };
+    // 两个时间都在较左侧，正相关
+    let events = [PosEvent { time: 400, range: (0, 690) }, PosEvent { time: 800, range: (0, 580) }];
+    let (a, b) = (marginal(events[0]), marginal(events[1]));
+    let joint = db.joint_prob(&scenario, &events).unwrap();
+    assert!(a > 0.1 && a < 0.9 && b > 0.1 && b < 0.9, "{a} {b}");
+    assert!(joint > a * b + 0.01 && joint <= a.min(b) + 1e-9);
+    // 各种命中情况的概率之和为 1，边缘概率与单独计算相同
+    let patterns = hit_patterns(db.get(&ZombieType::Catapult.into()).unwrap(), &scenario, &events).unwrap();
+    assert!((patterns.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!((patterns[1] + patterns[3] - a).abs() < 1e-9 && (patterns[2] + patterns[3] - b).abs() < 1e-9);
+}
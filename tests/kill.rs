@@ -0,0 +1,36 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::{Explosion, PosEvent, Scenario, Strike, ZombieDb, ZombieType};
+
+#[test]
+fn kill_prob_counts_damage_against_hp() {
+    let db = ZombieDb::builtin();
+    let zombie = ZombieType::GigaGargantuar;
+    let data = db.get(&zombie.into()).unwrap();
+    let scenario = Scenario::new(zombie, 0);
+    let center_x = Explosion::Cob.center_x(8.0);
+    let strikes: Vec<Strike> = [750, 800, 850, 900].iter().map(|&t| Strike::new(t, Explosion::Cob, center_x)).collect();
+    let damage = db.damage_distribution(&scenario, &strikes).unwrap();
+    assert!((damage.iter().map(|x| x.1).sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!(damage.windows(2).all(|x| x[0].0 < x[1].0) && damage.iter().all(|x| x.0 % 1800 == 0));
+    // 三炮伤害不足 6000
+    assert_eq!(db.kill_prob(&scenario, &strikes[..3]).unwrap(), 0.0);
+    // 四炮全部命中才能击杀
+    let events: Vec<PosEvent> = strikes.iter().map(|x| x.explosion.hit_event(data, x.time, x.center_x)).collect();
+    let kill = db.kill_prob(&scenario, &strikes).unwrap();
+    assert!(kill > 0.0);
+    assert!((kill - db.joint_prob(&scenario, &events).unwrap()).abs() < 1e-9);
+    assert!((kill - damage.last().filter(|x| x.0 == 7200).map_or(0.0, |x| x.1)).abs() < 1e-9);
+}
+
+#[test]
+fn custom_damage_is_used() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario::new(ZombieType::GigaGargantuar, 800);
+    let d = db.calculate(&scenario).unwrap();
+    let data = db.get(&ZombieType::GigaGargantuar.into()).unwrap();
+    let center_x = Explosion::Cob.center_x(8.0);
+    let strike = Strike { damage: 6000, ..Strike::new(800, Explosion::Cob, center_x) };
+    let kill = db.kill_prob(&scenario, &[strike]).unwrap();
+    assert!((kill - Explosion::Cob.hit_prob(data, &d, center_x)).abs() < 1e-9);
+}
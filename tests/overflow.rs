@@ -0,0 +1,67 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod common;
+
+use common::assert_dist_eq;
+use zombie_movement_calculator::common::{checked_product, product_f64};
+use zombie_movement_calculator::{search, Chill, Error, Num, PosEvent, Scenario, ZombieDb, ZombieType};
+
+// 内置数据中巨人僵尸的速度改为 17 位小数
+fn precise_gargantuar_db() -> ZombieDb {
+    let data: Vec<String> = include_str!("../assets/data.csv").lines().map(|x| {
+        if x.starts_with("Gargantuar,") {
+            x.replacen(",0.23,0.37,", ",0.23123456789012345,0.37123456789012345,", 1)
+        } else {
+            x.to_string()
+        }
+    }).collect();
+    return ZombieDb::from_csv(data.join("\n").as_bytes()).unwrap();
+}
+
+#[test]
+fn product_falls_back_to_big_rationals() {
+    let tiny = Num::new(1, 3_000_000_000);
+    assert_eq!(checked_product(&[tiny, tiny, tiny]), None);
+    let p = product_f64(&[tiny, tiny, tiny]);
+    assert!((p * 2.7e28 - 1.0).abs() < 1e-12, "{p}");
+    let half = Num::new(1, 2);
+    assert_eq!(checked_product(&[half, Num::from(3)]), Some(Num::new(3, 2)));
+    assert_eq!(product_f64(&[half, Num::from(3)]), 1.5);
+}
+
+#[test]
+fn exact_overflow_is_an_error() {
+    let db = &precise_gargantuar_db();
+    let scenario = Scenario::new(ZombieType::Gargantuar, 500);
+    assert!(matches!(db.calculate(&scenario), Err(Error::Overflow)));
+    assert!(matches!(db.calculate_series(&scenario, &[100, 500]), Err(Error::Overflow)));
+    assert!(matches!(db.joint_prob(&scenario, &[PosEvent { time: 500, range: (0, 900) }]), Err(Error::Overflow)));
+    assert!(matches!(search::reach_times(db, &scenario, 400, 0.5, (0, 1000)), Err(Error::Overflow)));
+    // 其他僵尸不受影响
+    assert!(db.calculate(&Scenario::new(ZombieType::Pogo, 500)).is_ok());
+}
+
+#[test]
+fn long_timeline_with_many_ices() {
+    // 后几次冰都在减速中，冻结 300cs；最后一次冰冻结到目标时间之后
+    let db = ZombieDb::builtin();
+    let ice_times: Vec<i64> = (0..8).map(|i| 100 + 900 * i).collect();
+    for zombie in [ZombieType::Catapult, ZombieType::Football] {
+        let long = Scenario { ice_times: ice_times.clone(), ..Scenario::new(zombie, 6600) };
+        let shorter = Scenario { ice_times: ice_times[..7].to_vec(), ..Scenario::new(zombie, 6399) };
+        let d = db.calculate(&long).unwrap();
+        assert!((d.prob_range(i64::MIN, i64::MAX) - 1.0).abs() < 1e-9, "{zombie}");
+        assert_dist_eq(&d, &db.calculate(&shorter).unwrap(), 0.0);
+    }
+}
+
+#[test]
+fn huge_target_time_is_an_error() {
+    // 目标时间接近 i64::MAX 时冻结、减速区间的右端取 i64::MAX，精确计算溢出时返回错误而不是 panic
+    let db = ZombieDb::builtin();
+    for zombie in [ZombieType::Pogo, ZombieType::Regular, ZombieType::Football, ZombieType::Catapult] {
+        let scenario = Scenario { ice_times: vec![100, i64::MAX - 1000], chills: vec![Chill::once(i64::MAX - 500)],
+                                  ..Scenario::new(zombie, i64::MAX) };
+        assert!(matches!(db.calculate(&scenario), Err(Error::Overflow)), "{zombie}");
+    }
+}
@@ -0,0 +1,90 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::search::{best_time, first_passage, hit_curve, reach_prob, reach_times, time_ranges};
+use zombie_movement_calculator::{Error, Explosion, Scenario, ZombieDb, ZombieType};
+
+fn reach_at(time: i64) -> f64 {
+    let scenario = Scenario { ice_times: vec![300], ..Scenario::new(ZombieType::Catapult, time) };
+    return reach_prob(ZombieDb::builtin(), &scenario, 600).unwrap();
+}
+
+#[test]
+fn reach_times_are_threshold_crossings() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario { ice_times: vec![300], ..Scenario::new(ZombieType::Catapult, 0) };
+    let times = reach_times(db, &scenario, 600, 0.5, (1, 3000)).unwrap();
+    let (first, last) = (times.first.unwrap(), times.last.unwrap());
+    assert!(reach_at(first) > 0.5 && reach_at(first - 1) <= 0.5);
+    assert!(1.0 - reach_at(last) > 0.5 && 1.0 - reach_at(last + 1) <= 0.5);
+    // 阈值越低，最早时间越早、最晚时间越晚
+    let loose = reach_times(db, &scenario, 600, 0.01, (1, 3000)).unwrap();
+    assert!(loose.first.unwrap() < first && loose.last.unwrap() > last);
+    // 搜索范围内不可能到达
+    assert_eq!(reach_times(db, &scenario, 600, 0.5, (1, 100)).unwrap().first, None);
+}
+
+#[test]
+fn reach_times_scan_non_monotone_zombies() {
+    let db = ZombieDb::builtin();
+    // 读报僵尸的动画会后退，舞王秘籍僵尸随机游走，到达概率不单调，结果应与逐个时间判断相同
+    for (zombie, x, window) in [(ZombieType::Newspaper, 760, (100, 300)), (ZombieType::DCFast, 740, (1, 500))] {
+        let scenario = Scenario::new(zombie, 0);
+        let curve: Vec<(i64, f64)> = (window.0..=window.1)
+            .map(|t| (t, reach_prob(db, &Scenario { time: t, ..scenario.clone() }, x).unwrap()))
+            .collect();
+        let times = reach_times(db, &scenario, x, 0.3, window).unwrap();
+        assert_eq!(times.first, curve.iter().find(|p| p.1 > 0.3).map(|p| p.0));
+        assert_eq!(times.last, curve.iter().rfind(|p| 1.0 - p.1 > 0.3).map(|p| p.0));
+        assert!(times.first.is_some() && times.last.is_some());
+    }
+}
+
+#[test]
+fn first_passage_matches_reach_probabilities() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario { ice_times: vec![300], ..Scenario::new(ZombieType::Catapult, 0) };
+    let passage = first_passage(db, &scenario, 600, (1, 3000)).unwrap();
+    assert!((passage.dist.iter().sum::<f64>() + passage.remaining - 1.0).abs() < 1e-9);
+    assert!(reach_at(passage.offset - 1) == 0.0 && passage.dist[0] > 0.0);
+    let mut cdf = 0.0;
+    for (i, p) in passage.dist.iter().enumerate() {
+        cdf += p;
+        assert!((cdf - reach_at(passage.offset + i as i64)).abs() < 1e-9);
+    }
+    // 搜索范围结束时仍未到达
+    let early = first_passage(db, &scenario, 600, (1, passage.offset + 10)).unwrap();
+    assert!((early.remaining - (1.0 - reach_at(passage.offset + 10))).abs() < 1e-9);
+}
+
+#[test]
+fn first_passage_rejects_non_monotone_zombies() {
+    let db = ZombieDb::builtin();
+    for zombie in [ZombieType::Newspaper, ZombieType::DCFast, ZombieType::DCSlow] {
+        let scenario = Scenario::new(zombie, 0);
+        assert!(matches!(first_passage(db, &scenario, 600, (1, 3000)), Err(Error::NotMonotone(_))));
+    }
+    assert!(first_passage(db, &Scenario::new(ZombieType::Dancing, 0), 760, (1, 1000)).is_ok());
+}
+
+#[test]
+fn timing_search_picks_best_hit_time() {
+    let db = ZombieDb::builtin();
+    let scenario = Scenario::new(ZombieType::Catapult, 0);
+    let data = db.get(&ZombieType::Catapult.into()).unwrap();
+    let center_x = Explosion::Cob.center_x(5.0);
+    let curve = hit_curve(db, &scenario, Explosion::Cob, center_x, (1, 3000)).unwrap();
+    assert_eq!(curve.len(), 3000);
+    for &(t, p) in curve.iter().step_by(100) {
+        let d = db.calculate(&Scenario { time: t, ..scenario.clone() }).unwrap();
+        assert!((p - Explosion::Cob.hit_prob(data, &d, center_x)).abs() < 1e-12);
+    }
+    let (best, p) = best_time(&curve).unwrap();
+    assert!(curve.iter().all(|x| x.1 <= p) && curve.iter().find(|x| x.1 == p).unwrap().0 == best);
+    // 概率先升后降，超过阈值的时间是一个区间
+    let ranges = time_ranges(&curve, 0.5);
+    assert_eq!(ranges.len(), 1);
+    assert!(ranges[0].0 <= best && best <= ranges[0].1);
+    assert_eq!(best_time(&[(1, 0.5), (2, 0.8), (3, 0.8)]), Some((2, 0.8)));
+    assert_eq!(time_ranges(&[(1, 0.6), (2, 0.4), (3, 0.6), (4, 0.7)], 0.5), vec![(1, 1), (3, 4)]);
+    assert_eq!(best_time(&[]), None);
+}
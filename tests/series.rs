@@ -0,0 +1,52 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod common;
+
+use common::assert_dist_eq;
+use std::time::{Duration, Instant};
+use zombie_movement_calculator::{Scenario, ZombieDb, ZombieType};
+
+// 批量计算的每个时间都与单独计算相同
+fn check_series(scenario: &Scenario, times: &[i64]) {
+    let db = ZombieDb::builtin();
+    let series = db.calculate_series(scenario, times).unwrap();
+    for (&time, d) in times.iter().zip(&series) {
+        let single = db.calculate(&Scenario { time: time, ..scenario.clone() }).unwrap();
+        assert_dist_eq(d, &single, 1e-12);
+    }
+}
+
+#[test]
+fn series_equals_single_queries() {
+    for zombie in [ZombieType::Football, ZombieType::GigaGargantuar, ZombieType::Regular, ZombieType::Newspaper] {
+        check_series(&Scenario::new(zombie, 0), &[100, 350, 600, 601]);
+    }
+}
+
+#[test]
+fn series_with_ice_equals_single_queries() {
+    let scenario = Scenario { ice_times: vec![200], ..Scenario::new(ZombieType::Football, 0) };
+    check_series(&scenario, &[150, 400, 700]);
+}
+
+#[test]
+fn consecutive_times_equal_single_queries() {
+    // 每个时间的 k 分段都不同，共用的分段只计算一次
+    let times: Vec<i64> = (300..=320).collect();
+    check_series(&Scenario::new(ZombieType::Regular, 0), &times);
+    check_series(&Scenario { ice_times: vec![250], ..Scenario::new(ZombieType::Newspaper, 0) }, &times);
+}
+
+// 耗时受机器负载影响，不在默认测试中运行，用 cargo test --release -- --ignored 检查
+#[test]
+#[ignore = "性能测试"]
+fn long_window_is_fast() {
+    // 逐 cs 计算时每个目标时间的 k 分段都不同，分段数随时间平方增长，每一段的计算和分段本身都必须足够快
+    let db = ZombieDb::builtin();
+    let times: Vec<i64> = (0..=600).collect();
+    let start = Instant::now();
+    let series = db.calculate_series(&Scenario::new(ZombieType::Regular, 0), &times).unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(series.len(), times.len());
+    assert!(elapsed < Duration::from_secs(20), "逐 cs 计算 600cs 用时 {elapsed:?}");
+}
@@ -0,0 +1,14 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::table::{write_table_csv, write_table_markdown};
+
+#[test]
+fn quantile_headers_have_no_float_artifacts() {
+    let quantiles = [0.0, 0.05, 0.5, 0.999, 1.0 / 3.0, 1.0];
+    let mut csv = Vec::new();
+    write_table_csv(&mut csv, &quantiles, &[]).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "zombie,time,min,max,p0,p5,p50,p99.9,p33.333333,p100\n");
+    let mut markdown = Vec::new();
+    write_table_markdown(&mut markdown, &[0.05, 0.95], &[]).unwrap();
+    assert!(String::from_utf8(markdown).unwrap().starts_with("| zombie | time | min | max | p5 | p95 |\n"));
+}
@@ -0,0 +1,89 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::validate::{validate, Issue, Severity};
+use zombie_movement_calculator::{Error, Scenario, ZombieDb, ZombieType};
+
+const DATA: &str = include_str!("../assets/data.csv");
+
+// 替换某只僵尸一行中的内容后检查
+fn validate_row(zombie: &str, from: &str, to: &str) -> Vec<Issue> {
+    let row = DATA.lines().find(|x| x.starts_with(&format!("{zombie},"))).unwrap();
+    assert!(row.contains(from), "{zombie} 一行中没有 {from}");
+    return validate(DATA.replacen(row, &row.replacen(from, to, 1), 1).as_bytes());
+}
+
+fn errors(issues: &[Issue]) -> Vec<(&str, &str)> {
+    return issues.iter()
+        .filter(|x| x.severity == Severity::Error)
+        .map(|x| (x.zombie.as_str(), x.message.as_str()))
+        .collect();
+}
+
+#[test]
+fn builtin_data_has_no_errors() {
+    let issues = validate(DATA.as_bytes());
+    assert!(errors(&issues).is_empty(), "{issues:?}");
+    // 读报僵尸的动画轨道会后退
+    assert!(issues.iter().any(|x| x.zombie == "Newspaper" && x.severity == Severity::Warning));
+}
+
+#[test]
+fn inconsistent_rows_are_reported() {
+    let check = |zombie: &str, from: &str, to: &str, message: &str| {
+        let issues = validate_row(zombie, from, to);
+        assert!(errors(&issues).iter().any(|x| x.0 == zombie && x.1.contains(message)), "{zombie}: {issues:?}");
+    };
+    check("Pogo", "0.45,0.45", "0.45,0.23", "速度范围的左端大于右端");
+    check("Catapult", "825,834,825,834", "825,834,834,825", "大波出生范围的左端大于右端");
+    check("Regular", ";", ",", "regular 需要两条用 ; 分隔的动画轨道");
+    check("Regular", ";", ",41;", "长度不同");
+    check("Balloon", "true,false", "false,true", "免疫减速但不免疫冰冻");
+    check("Zomboni", "true,true", "false,false", "zomboni");
+    check("Flag", "animation,\"-9.8,", "animation,\"-9.8x,", "无效的数值");
+    check("Ladder", "animation", "walking", "未知的移动方式");
+}
+
+#[test]
+fn spawn_outside_grid_is_a_warning() {
+    let issues = validate_row("PoleVaulting", "870,879,870,879", "870,879,880,900");
+    assert!(errors(&issues).is_empty(), "{issues:?}");
+    assert!(issues.iter().any(|x| x.zombie == "PoleVaulting" && x.severity == Severity::Warning && x.message.contains("超出 0-879")));
+    // 只有警告的数据可以读取
+    let data = DATA.replacen("PoleVaulting,870,879,870,879", "PoleVaulting,870,879,880,900", 1);
+    let db = ZombieDb::from_csv(data.as_bytes()).unwrap();
+    let d = db.calculate(&Scenario { hugewave: true, ..Scenario::new(ZombieType::PoleVaulting, 0) }).unwrap();
+    assert!((d.prob_range(880, 900) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn all_problems_are_reported_at_once() {
+    let pogo = DATA.lines().find(|x| x.starts_with("Pogo,")).unwrap();
+    let data = DATA.replacen("constant,,0.45,0.45", "constant,,0.45,0.23", 1)
+                   .replacen("Catapult,825,834", "Catapult,834,825", 1) + pogo + "\nBroken,1,2\n";
+    let issues = validate(data.as_bytes());
+    let errors = errors(&issues);
+    assert!(errors.contains(&("Pogo", "出现了多次")));
+    assert!(errors.iter().any(|x| x.0 == "Catapult"));
+    assert!(errors.iter().any(|x| x.0.starts_with("第 ")));
+    assert_eq!(errors.iter().filter(|x| x.0 == "Pogo").count(), 2);
+}
+
+#[test]
+fn out_of_range_decimals_are_rejected() {
+    let check = |to: &str, expected: fn(&Error) -> bool| {
+        let issues = validate_row("Pogo", "0.45,0.45", to);
+        assert!(errors(&issues).iter().any(|x| x.0 == "Pogo"), "{to}: {issues:?}");
+        assert!(!errors(&issues).iter().any(|x| x.1.contains("左端大于右端")), "{to}: {issues:?}");
+        let data = DATA.replacen("constant,,0.45,0.45", &format!("constant,,{to}"), 1);
+        match ZombieDb::from_csv(data.as_bytes()) {
+            Err(e) => assert!(expected(&e), "{to}: {e}"),
+            Ok(_) => panic!("{to} 应当有误"),
+        }
+    };
+    check("0.1234567890123456789,0.45", |e| matches!(e, Error::Overflow));
+    check("0.45,999999999999999999.5", |e| matches!(e, Error::Overflow));
+    check("1.-5,1.5", |e| matches!(e, Error::InvalidNumber(_)));
+    // 末尾的 0 不计入小数位数
+    let data = DATA.replacen("constant,,0.45,0.45", "constant,,0.4500000000000000000000,0.45", 1);
+    assert!(ZombieDb::from_csv(data.as_bytes()).is_ok());
+}
@@ -0,0 +1,36 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use zombie_movement_calculator::wave::{can_spawn, composition, mixture};
+use zombie_movement_calculator::{Scenario, Scene, ZombieDb, ZombieType};
+
+#[test]
+fn composition_follows_summon_weights() {
+    let db = ZombieDb::builtin();
+    let all = db.names();
+    for scene in [Scene::Yard, Scene::Pool, Scene::Roof] {
+        for (hugewave, early) in [(false, false), (true, false), (false, true)] {
+            let comp = composition(db, all, scene, hugewave, early).unwrap();
+            assert!((comp.iter().map(|x| x.1).sum::<f64>() - 1.0).abs() < 1e-12);
+            assert!(comp.iter().all(|(x, _)| can_spawn(db.get(x).unwrap(), scene, early)));
+        }
+    }
+    let comp = composition(db, &[ZombieType::Regular.into(), ZombieType::GigaGargantuar.into()], Scene::Yard, false, false).unwrap();
+    let (regular, giga) = (db.get(&ZombieType::Regular.into()).unwrap(), db.get(&ZombieType::GigaGargantuar.into()).unwrap());
+    let total = f64::from(regular.summon_weight_normal + giga.summon_weight_normal);
+    assert_eq!(comp, vec![(ZombieType::Regular.into(), f64::from(regular.summon_weight_normal) / total),
+                          (ZombieType::GigaGargantuar.into(), f64::from(giga.summon_weight_normal) / total)]);
+}
+
+#[test]
+fn mixture_weights_distributions() {
+    let db = ZombieDb::builtin();
+    let comp = vec![(ZombieType::Pogo.into(), 0.25), (ZombieType::Catapult.into(), 0.75)];
+    let scenario = Scenario::new(ZombieType::Pogo, 600);
+    let mixed = mixture(db, &comp, &scenario).unwrap();
+    let pogo = db.calculate(&scenario).unwrap();
+    let catapult = db.calculate(&Scenario::new(ZombieType::Catapult, 600)).unwrap();
+    for x in 0..880 {
+        assert!((mixed.prob(x) - (0.25 * pogo.prob(x) + 0.75 * catapult.prob(x))).abs() < 1e-12);
+    }
+    assert_eq!((mixed.min, mixed.max), (pogo.min.min(catapult.min), pogo.max.max(catapult.max)));
+}